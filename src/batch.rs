@@ -0,0 +1,224 @@
+use log::{debug, warn};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::time::{Duration, Instant};
+
+use super::sudoku::solver::{Outcome, Solver};
+use super::sudoku::{Alphabet, Format, Report, Sudoku};
+
+/// Statistics of a batch run.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub solved: u32,
+    pub failed: u32,
+    pub tries: u64,
+    pub elapsed: Duration,
+}
+
+impl Summary {
+    pub fn total(&self) -> u32 {
+        self.solved + self.failed
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Solved {} of {} sudokus ({} failed) with {} tries in {:.3}s.",
+            self.solved,
+            self.total(),
+            self.failed,
+            self.tries,
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
+/// Solves every sudoku of `input`, which contains one sudoku per line in the
+/// compact line format or the JSON format. Empty lines and lines containing
+/// `#` are skipped. Sudokus in the line format are read with `alphabet` if it
+/// fits their size.
+///
+/// Each sudoku is solved with a fresh solver returned by `create_solver` and
+/// its solution is written to `out` in the given format. In the line and
+/// JSON format every solution is written as a single line, in the boxed
+/// format solutions are separated by empty lines. Sudokus that cannot be
+/// solved are written unsolved and sudokus that cannot be read are written
+/// unchanged (or as `null` in the JSON format), so that the n-th solution
+/// always belongs to the n-th sudoku in the input.
+pub fn run<R, W, F>(
+    input: R,
+    out: &mut W,
+    format: Format,
+    algorithm: &str,
+    alphabet: Option<&Alphabet>,
+    create_solver: F,
+) -> io::Result<Summary>
+where
+    R: BufRead,
+    W: Write,
+    F: Fn() -> Box<dyn Solver>,
+{
+    let start = Instant::now();
+    let mut summary = Summary::default();
+
+    for (line_index, line) in input.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.contains('#') {
+            continue;
+        }
+
+        let parsed = if line.starts_with('{') {
+            Sudoku::from_json(line)
+        } else {
+            Sudoku::from_line(line, line_index + 1, alphabet)
+        };
+        let sudoku = match parsed {
+            Ok(sudoku) => sudoku,
+            Err(err) => {
+                warn!("Skipping sudoku: {}", err);
+                summary.failed += 1;
+                match format {
+                    Format::Json => writeln!(out, "null")?,
+                    _ => writeln!(out, "{}", line)?,
+                }
+                continue;
+            }
+        };
+
+        let mut solver = create_solver();
+        let solve_start = Instant::now();
+        let sudoku = solver.solve(sudoku);
+        let elapsed = solve_start.elapsed();
+        let success = solver.is_success();
+        summary.tries += u64::from(solver.get_tries());
+        if success {
+            debug!(
+                "Solved sudoku in line {} with {} tries.",
+                line_index + 1,
+                solver.get_tries()
+            );
+            summary.solved += 1;
+        } else if solver.get_outcome() == Some(Outcome::Unsolvable) {
+            warn!("Sudoku in line {} has no solution.", line_index + 1);
+            summary.failed += 1;
+        } else {
+            warn!(
+                "Failed to solve sudoku in line {} within {} tries.",
+                line_index + 1,
+                solver.get_tries()
+            );
+            summary.failed += 1;
+        }
+
+        match format {
+            Format::Json => {
+                let mut report =
+                    Report::from_run(&sudoku, success, algorithm, solver.get_tries(), elapsed);
+                report.winner = solver.get_winner().map(String::from);
+                report.seed = solver.get_seed();
+                serde_json::to_writer(&mut *out, &report)?;
+                writeln!(out)?;
+            }
+            _ => {
+                let grid = match success {
+                    true => sudoku.grid,
+                    false => sudoku.get_unsolved(),
+                };
+                if format == Format::Boxed && summary.total() > 1 {
+                    writeln!(out)?;
+                }
+                writeln!(out, "{}", grid.fmt_as(format))?;
+            }
+        }
+    }
+
+    out.flush()?;
+    summary.elapsed = start.elapsed();
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::solver::Backtracing;
+
+    #[test]
+    fn it_should_solve_all_sudokus() {
+        let input = "# comment\n\
+            ...26.7.168..7..9.19...45..82.1...4...46.29...5...3.28..93...74.4..5..367.3.18...\n\
+            \n\
+            1..489..673.....4......1295..712.6..5..7.3..8..6.957..9146......2.....378..512..4\n";
+        let mut out: Vec<u8> = vec![];
+        let summary = run(
+            input.as_bytes(),
+            &mut out,
+            Format::Line,
+            "backtracing",
+            None,
+            || Box::new(Backtracing::new(100000)),
+        )
+        .unwrap();
+
+        assert_eq!((summary.solved, summary.failed), (2, 0));
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "435269781682571493197834562826195347374682915951743628519326874248957136763418259\n\
+            152489376739256841468371295387124659591763428246895713914637582625948137873512964\n"
+        );
+    }
+
+    #[test]
+    fn it_should_keep_failed_sudokus_in_place() {
+        let input =
+            "...26.7.168..7..9.19...45..82.1...4...46.29...5...3.28..93...74.4..5..367.3.18..\n\
+            ...26.7.168..7..9.19...45..82.1...4...46.29...5...3.28..93...74.4..5..367.3.18...\n";
+        let mut out: Vec<u8> = vec![];
+        let summary = run(
+            input.as_bytes(),
+            &mut out,
+            Format::Line,
+            "backtracing",
+            None,
+            || Box::new(Backtracing::new(10)),
+        )
+        .unwrap();
+
+        assert_eq!((summary.solved, summary.failed), (0, 2));
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "...26.7.168..7..9.19...45..82.1...4...46.29...5...3.28..93...74.4..5..367.3.18..\n\
+            ...26.7.168..7..9.19...45..82.1...4...46.29...5...3.28..93...74.4..5..367.3.18...\n"
+        );
+    }
+
+    #[test]
+    fn it_should_write_json_lines() {
+        let input =
+            "...26.7.168..7..9.19...45..82.1...4...46.29...5...3.28..93...74.4..5..367.3.18...\n\
+            {\"puzzle\": [[1, 2]]}\n";
+        let mut out: Vec<u8> = vec![];
+        let summary = run(
+            input.as_bytes(),
+            &mut out,
+            Format::Json,
+            "backtracing",
+            None,
+            || Box::new(Backtracing::new(100000)),
+        )
+        .unwrap();
+
+        assert_eq!((summary.solved, summary.failed), (1, 1));
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        let report: Report = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(report.algorithm.as_deref(), Some("backtracing"));
+        assert_eq!(
+            report.solution.unwrap().fmt_line(),
+            "435269781682571493197834562826195347374682915951743628519326874248957136763418259"
+        );
+        assert_eq!(lines[1], "null");
+    }
+}
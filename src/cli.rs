@@ -1,5 +1,7 @@
 use clap::{App, Arg};
 
+use super::sudoku::Alphabet;
+
 pub fn configure_parser() -> App<'static, 'static> {
     App::new(crate_name!())
         .about(crate_description!())
@@ -8,16 +10,121 @@ pub fn configure_parser() -> App<'static, 'static> {
         .arg(
             Arg::with_name("INPUT")
                 .short("i")
-                .help("Sets the file to read the sudoku from")
+                .help("Sets the file to read the sudoku from, use - to read from stdin")
                 .required(true)
                 .index(1),
         )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .help("Sets the file to write the solution to instead of stdout"),
+        )
         .arg(
             Arg::with_name("show-unsolved")
                 .long("show-unsolved")
                 .required(false)
                 .help("Shows the unsolved sudoku next to the solution"),
         )
+        .arg(
+            Arg::with_name("batch")
+                .long("batch")
+                .required(false)
+                .conflicts_with("show-unsolved")
+                .help(
+                    "Solves every sudoku of the input file, which must contain one sudoku per \
+                    line in the compact line or JSON format, and prints one solution per line",
+                ),
+        )
+        .arg(
+            Arg::with_name("validate")
+                .long("validate")
+                .required(false)
+                .conflicts_with_all(&["batch", "show-unsolved"])
+                .help(
+                    "Checks the sudoku for duplicate values instead of solving it and lists \
+                    every conflict",
+                ),
+        )
+        .arg(
+            Arg::with_name("candidates")
+                .long("candidates")
+                .required(false)
+                .conflicts_with_all(&["batch", "validate", "show-unsolved", "format"])
+                .help(
+                    "Prints the pencil marks of the sudoku instead of solving it, which show \
+                    the candidates of every field",
+                ),
+        )
+        .arg(
+            Arg::with_name("count")
+                .long("count")
+                .required(false)
+                .conflicts_with_all(&["batch", "validate", "candidates", "show-unsolved"])
+                .help(
+                    "Counts the solutions of the sudoku up to --count-limit instead of solving \
+                    it and reports whether the solution is unique",
+                ),
+        )
+        .arg(
+            Arg::with_name("count-limit")
+                .long("count-limit")
+                .takes_value(true)
+                .default_value("2")
+                .validator(is_at_least(2))
+                .help(
+                    "Stops counting solutions after this number has been found, at least 2 \
+                    are needed to tell whether the solution is unique.",
+                ),
+        )
+        .arg(
+            Arg::with_name("solutions")
+                .long("solutions")
+                .takes_value(true)
+                .value_name("N")
+                .validator(is_at_least(1))
+                .conflicts_with_all(&["batch", "validate", "candidates", "count"])
+                .help(
+                    "Prints the first N solutions of the sudoku instead of a single one, which \
+                    shows how an ambiguous sudoku can be completed",
+                ),
+        )
+        .arg(
+            Arg::with_name("dimacs")
+                .long("dimacs")
+                .required(false)
+                .conflicts_with_all(&["batch", "validate", "candidates", "count", "solutions"])
+                .help(
+                    "Prints the sudoku as a boolean formula in the DIMACS CNF format instead of \
+                    solving it, which can be solved with external SAT solvers",
+                ),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["boxed", "line", "json"])
+                .help(
+                    "Selects the format in which the sudoku will be printed. \
+                    [default: boxed, line in batch mode]",
+                ),
+        )
+        .arg(
+            Arg::with_name("alphabet")
+                .long("alphabet")
+                .takes_value(true)
+                .validator(|name| {
+                    Alphabet::from_name(&name)
+                        .map(|_| ())
+                        .map_err(|err| err.to_string())
+                })
+                .help(
+                    "Sets the symbols of the values, either digits (1-9), hex (0-F), letters \
+                    (A-Y) or a custom list of symbols such as WORDSPLAY. \
+                    [default: 1-9 followed by A-Z as needed]",
+                ),
+        )
         .arg(
             Arg::with_name("max-tries")
                 .long("max-tries")
@@ -25,13 +132,249 @@ pub fn configure_parser() -> App<'static, 'static> {
                 .default_value("100000")
                 .help("Defines the maximum number of tries to iteratively solve the sudoku."),
         )
+        .arg(
+            Arg::with_name("timeout")
+                .long("timeout")
+                .takes_value(true)
+                .value_name("SECONDS")
+                .validator(|seconds| match seconds.parse::<f64>() {
+                    Ok(seconds) if seconds > 0.0 && seconds.is_finite() => Ok(()),
+                    _ => Err(String::from(
+                        "the timeout must be a positive number of seconds",
+                    )),
+                })
+                .help(
+                    "Stops the solver after this number of seconds, e.g. 0.5. In batch mode the \
+                    timeout applies to every sudoku.",
+                ),
+        )
+        .arg(
+            Arg::with_name("cooling")
+                .long("cooling")
+                .possible_values(&["constant", "geometric", "linear", "adaptive"])
+                .default_value("constant")
+                .help(
+                    "Selects how the temperature of the montecarlo algorithm decreases from \
+                    --start-temperature to --end-temperature.",
+                ),
+        )
+        .arg(
+            Arg::with_name("start-temperature")
+                .long("start-temperature")
+                .default_value("0.15")
+                .validator(is_positive)
+                .help("Sets the initial temperature of the montecarlo algorithm."),
+        )
+        .arg(
+            Arg::with_name("end-temperature")
+                .long("end-temperature")
+                .default_value("0.01")
+                .validator(is_positive)
+                .help(
+                    "Sets the final temperature of the montecarlo algorithm, which must not be \
+                    above --start-temperature.",
+                ),
+        )
+        .arg(
+            Arg::with_name("cooling-steps")
+                .long("cooling-steps")
+                .value_name("TRIES")
+                .default_value("100000")
+                .validator(is_at_least(1))
+                .help(
+                    "Sets the number of tries in which the geometric and linear cooling \
+                    schedules reach the end temperature.",
+                ),
+        )
+        .arg(
+            Arg::with_name("reheat")
+                .long("reheat")
+                .value_name("TRIES")
+                .takes_value(true)
+                .validator(is_at_least(1))
+                .help(
+                    "Resets the temperature of the montecarlo algorithm to the start temperature \
+                    if the energy has not decreased for this number of tries",
+                ),
+        )
+        .arg(
+            Arg::with_name("replicas")
+                .long("replicas")
+                .value_name("N")
+                .default_value("8")
+                .validator(is_at_least(1))
+                .help(
+                    "Sets the number of replicas of the tempering algorithm, which run at \
+                    temperatures between --end-temperature and --start-temperature.",
+                ),
+        )
+        .arg(
+            Arg::with_name("exchange-interval")
+                .long("exchange-interval")
+                .value_name("TRIES")
+                .default_value("100")
+                .validator(is_at_least(1))
+                .help(
+                    "Sets the number of tries after which the replicas of the tempering \
+                    algorithm exchange their states.",
+                ),
+        )
+        .arg(
+            Arg::with_name("population")
+                .long("population")
+                .value_name("N")
+                .default_value("100")
+                .validator(is_at_least(2))
+                .help("Sets the number of individuals of the genetic algorithm."),
+        )
+        .arg(
+            Arg::with_name("mutation-rate")
+                .long("mutation-rate")
+                .value_name("RATE")
+                .default_value("0.5")
+                .validator(is_rate)
+                .help(
+                    "Sets the probability that a child of the genetic algorithm is mutated by \
+                    swapping two values of a parcel.",
+                ),
+        )
+        .arg(
+            Arg::with_name("elitism")
+                .long("elitism")
+                .value_name("N")
+                .default_value("2")
+                .validator(is_at_least(0))
+                .help(
+                    "Sets the number of the best individuals of the genetic algorithm that are \
+                    kept unchanged in the next generation, which must be less than \
+                    --population.",
+                ),
+        )
+        .arg(
+            Arg::with_name("tabu-tenure")
+                .long("tabu-tenure")
+                .value_name("TRIES")
+                .default_value("10")
+                .validator(is_at_least(1))
+                .help(
+                    "Sets the number of tries for which the tabu algorithm may not place a value \
+                    that has been removed from a field.",
+                ),
+        )
+        .arg(
+            Arg::with_name("restart-after")
+                .long("restart-after")
+                .value_name("TRIES")
+                .default_value("1000")
+                .validator(is_at_least(1))
+                .help(
+                    "Restarts the tabu algorithm from a random state if the energy has not \
+                    decreased for this number of tries.",
+                ),
+        )
+        .arg(
+            Arg::with_name("ants")
+                .long("ants")
+                .value_name("N")
+                .default_value("10")
+                .validator(is_at_least(1))
+                .help("Sets the number of ants per iteration of the aco algorithm."),
+        )
+        .arg(
+            Arg::with_name("evaporation")
+                .long("evaporation")
+                .value_name("RATE")
+                .default_value("0.9")
+                .validator(is_rate)
+                .help(
+                    "Sets the share of the pheromone of the aco algorithm that evaporates when \
+                    the best solution so far adds its pheromone.",
+                ),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .value_name("SEED")
+                .takes_value(true)
+                .help(
+                    "Sets the seed of the random numbers of the montecarlo, tempering, genetic, \
+                    tabu and aco algorithms, which reproduces a previous run. [default: random]",
+                ),
+        )
+        .arg(
+            Arg::with_name("progress")
+                .long("progress")
+                .conflicts_with_all(&["validate", "candidates", "count", "solutions", "dimacs"])
+                .help(
+                    "Prints the progress of the solver to stderr every --progress-interval tries, \
+                    e.g. the energy of a Montecarlo simulation",
+                ),
+        )
+        .arg(
+            Arg::with_name("progress-interval")
+                .long("progress-interval")
+                .value_name("TRIES")
+                .default_value("10000")
+                .validator(is_at_least(1))
+                .help("Sets the number of tries between two progress reports."),
+        )
         .arg(
             Arg::with_name("algorithm")
                 .long("algorithm")
-                .possible_values(&["backtracing", "montecarlo"])
+                .possible_values(&[
+                    "backtracing",
+                    "montecarlo",
+                    "dlx",
+                    "propagation",
+                    "sat",
+                    "tempering",
+                    "genetic",
+                    "tabu",
+                    "aco",
+                    "portfolio",
+                ])
                 .default_value("backtracing")
                 .help("Selects which algorithm will be used to solve the sudoku."),
         )
+        .arg(
+            Arg::with_name("portfolio")
+                .long("portfolio")
+                .value_name("ALGORITHMS")
+                .use_delimiter(true)
+                .possible_values(&[
+                    "backtracing",
+                    "montecarlo",
+                    "dlx",
+                    "propagation",
+                    "sat",
+                    "tempering",
+                    "genetic",
+                    "tabu",
+                    "aco",
+                ])
+                .default_value("backtracing,montecarlo,montecarlo,dlx,propagation,sat")
+                .help(
+                    "Comma separated list of algorithms that race against each other on separate \
+                    threads with the portfolio algorithm. The first solution wins.",
+                ),
+        )
+        .arg(
+            Arg::with_name("cell-order")
+                .long("cell-order")
+                .possible_values(&["mrv", "row-major"])
+                .default_value("mrv")
+                .help(
+                    "Selects the field to branch on with the propagation algorithm, either the \
+                    one with the minimum remaining values or the first empty one.",
+                ),
+        )
+        .arg(
+            Arg::with_name("value-order")
+                .long("value-order")
+                .possible_values(&["ascending", "descending", "least-constraining"])
+                .default_value("ascending")
+                .help("Selects the order in which the propagation algorithm tries values."),
+        )
         .arg(
             Arg::with_name("verbosity")
                 .short("v")
@@ -42,3 +385,30 @@ pub fn configure_parser() -> App<'static, 'static> {
                 ),
         )
 }
+
+/// Validates that an argument is a positive number.
+fn is_positive(value: String) -> Result<(), String> {
+    match value.parse::<f64>() {
+        Ok(number) if number > 0.0 && number.is_finite() => Ok(()),
+        _ => Err(format!("{} is not a positive number", value)),
+    }
+}
+
+/// Validates that an argument is a rate between 0 and 1.
+fn is_rate(value: String) -> Result<(), String> {
+    match value.parse::<f32>() {
+        Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(()),
+        _ => Err(format!("{} is not a rate between 0 and 1", value)),
+    }
+}
+
+/// Returns a validator for whole numbers of at least `min`.
+fn is_at_least(min: u32) -> impl Fn(String) -> Result<(), String> {
+    move |value| match value.parse::<u32>() {
+        Ok(number) if number >= min => Ok(()),
+        _ => Err(format!(
+            "{} is not a whole number of at least {}",
+            value, min
+        )),
+    }
+}
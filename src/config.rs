@@ -1,24 +1,226 @@
-use clap::{value_t_or_exit, ArgMatches};
+use clap::{value_t_or_exit, ArgMatches, Error};
 use log::info;
+use std::time::Duration;
 
+use super::sudoku::solver::{CellOrder, Cooling, Schedule, ValueOrder};
+use super::sudoku::{Alphabet, Format};
+
+#[derive(Clone)]
 pub struct Config {
     pub input_file: String,
+    pub output_file: Option<String>,
     pub max_tries: u32,
+    pub timeout: Option<Duration>,
+    pub seed: Option<u64>,
+    pub schedule: Schedule,
+    pub replicas: usize,
+    pub exchange_interval: u32,
+    pub population: usize,
+    pub mutation_rate: f32,
+    pub elitism: usize,
+    pub tabu_tenure: u32,
+    pub restart_after: u32,
+    pub ants: usize,
+    pub evaporation: f32,
+    pub progress: bool,
+    pub progress_interval: u32,
     pub show_unsolved: bool,
+    pub format: Format,
+    pub alphabet: Option<Alphabet>,
+    pub algorithm: String,
+    pub portfolio: Vec<String>,
+    pub cell_order: CellOrder,
+    pub value_order: ValueOrder,
+    pub batch: bool,
+    pub validate: bool,
+    pub candidates: bool,
+    pub count: bool,
+    pub count_limit: usize,
+    pub solutions: Option<usize>,
+    pub dimacs: bool,
 }
 
 impl Config {
     pub fn from_matches(matches: &ArgMatches) -> Self {
         let input_file = String::from(matches.value_of("INPUT").unwrap());
         info!("Using input file: {}", input_file);
+        let output_file = matches.value_of("output").map(String::from);
+        if let Some(output_file) = &output_file {
+            info!("Using output file: {}", output_file);
+        }
         let max_tries = value_t_or_exit!(matches.value_of("max-tries"), u32);
         info!("Using maximum number of tries: {}", max_tries);
+        // The timeout has already been validated by the parser.
+        let timeout = matches
+            .value_of("timeout")
+            .map(|seconds| Duration::from_secs_f64(seconds.parse().unwrap()));
+        if let Some(timeout) = timeout {
+            info!("Using timeout: {:?}", timeout);
+        }
+        let seed = matches
+            .value_of("seed")
+            .map(|_| value_t_or_exit!(matches.value_of("seed"), u64));
+        if let Some(seed) = seed {
+            info!("Using seed: {}", seed);
+        }
+        let progress = matches.is_present("progress");
+        let progress_interval = value_t_or_exit!(matches.value_of("progress-interval"), u32);
+        if progress {
+            info!("Reporting progress every {} tries", progress_interval);
+        }
         let show_unsolved = matches.is_present("show-unsolved");
+        let batch = matches.is_present("batch");
+        let validate = matches.is_present("validate");
+        let candidates = matches.is_present("candidates");
+        let count = matches.is_present("count");
+        let count_limit = value_t_or_exit!(matches.value_of("count-limit"), usize);
+        if count {
+            info!("Using solution count limit: {}", count_limit);
+        }
+        let dimacs = matches.is_present("dimacs");
+        let solutions = matches
+            .value_of("solutions")
+            .map(|_| value_t_or_exit!(matches.value_of("solutions"), usize));
+        let format = match matches.value_of("format") {
+            Some("boxed") => Format::Boxed,
+            Some("line") => Format::Line,
+            Some("json") => Format::Json,
+            // Batch mode prints one solution per line by default.
+            _ if batch => Format::Line,
+            _ => Format::Boxed,
+        };
+
+        // The alphabet has already been validated by the parser.
+        let alphabet = matches
+            .value_of("alphabet")
+            .map(|name| Alphabet::from_name(name).unwrap());
+        if let Some(alphabet) = &alphabet {
+            info!("Using alphabet: {}", alphabet);
+        }
+        let algorithm = String::from(matches.value_of("algorithm").unwrap());
+        info!("Using algorithm: {}", algorithm);
+        let portfolio: Vec<String> = matches
+            .values_of("portfolio")
+            .unwrap()
+            .map(String::from)
+            .collect();
+        if algorithm == "portfolio" {
+            info!("Using portfolio: {}", portfolio.join(", "));
+        }
+        let cell_order = match matches.value_of("cell-order") {
+            Some("row-major") => CellOrder::RowMajor,
+            _ => CellOrder::MinimumRemainingValues,
+        };
+        let value_order = match matches.value_of("value-order") {
+            Some("descending") => ValueOrder::Descending,
+            Some("least-constraining") => ValueOrder::LeastConstraining,
+            _ => ValueOrder::Ascending,
+        };
+        if algorithm == "propagation" {
+            info!("Using cell order: {:?}", cell_order);
+            info!("Using value order: {:?}", value_order);
+        }
+        let start_temperature = value_t_or_exit!(matches.value_of("start-temperature"), f32);
+        let end_temperature = value_t_or_exit!(matches.value_of("end-temperature"), f32);
+        if start_temperature < end_temperature {
+            Error::value_validation_auto(String::from(
+                "the start temperature must not be below the end temperature",
+            ))
+            .exit();
+        }
+        let cooling = match matches.value_of("cooling") {
+            Some("geometric") => Cooling::Geometric,
+            Some("linear") => Cooling::Linear,
+            Some("adaptive") => Cooling::Adaptive,
+            _ => Cooling::Constant,
+        };
+        let schedule = match cooling {
+            Cooling::Constant => Schedule::constant(start_temperature),
+            _ => Schedule {
+                cooling,
+                start_temperature,
+                end_temperature,
+                steps: value_t_or_exit!(matches.value_of("cooling-steps"), u32),
+                reheat_after: matches
+                    .value_of("reheat")
+                    .map(|_| value_t_or_exit!(matches.value_of("reheat"), u32)),
+            },
+        };
+        if algorithm == "montecarlo" {
+            info!("Using annealing schedule: {:?}", schedule);
+        }
+        let population = value_t_or_exit!(matches.value_of("population"), usize);
+        let mutation_rate = value_t_or_exit!(matches.value_of("mutation-rate"), f32);
+        let elitism = value_t_or_exit!(matches.value_of("elitism"), usize);
+        if elitism >= population {
+            Error::value_validation_auto(String::from(
+                "the elitism must be less than the population",
+            ))
+            .exit();
+        }
+        if algorithm == "genetic" {
+            info!(
+                "Using a population of {} with a mutation rate of {} and elitism of {}",
+                population, mutation_rate, elitism
+            );
+        }
+        let tabu_tenure = value_t_or_exit!(matches.value_of("tabu-tenure"), u32);
+        let restart_after = value_t_or_exit!(matches.value_of("restart-after"), u32);
+        if algorithm == "tabu" {
+            info!(
+                "Using a tabu tenure of {} tries and restarts after {} tries",
+                tabu_tenure, restart_after
+            );
+        }
+        let ants = value_t_or_exit!(matches.value_of("ants"), usize);
+        let evaporation = value_t_or_exit!(matches.value_of("evaporation"), f32);
+        if algorithm == "aco" {
+            info!(
+                "Using {} ants per iteration with an evaporation of {}",
+                ants, evaporation
+            );
+        }
+        let replicas = value_t_or_exit!(matches.value_of("replicas"), usize);
+        let exchange_interval = value_t_or_exit!(matches.value_of("exchange-interval"), u32);
+        if algorithm == "tempering" {
+            info!(
+                "Using {} replicas with an exchange interval of {} tries",
+                replicas, exchange_interval
+            );
+        }
 
         Config {
             input_file,
+            output_file,
             max_tries,
+            timeout,
+            seed,
+            schedule,
+            replicas,
+            exchange_interval,
+            population,
+            mutation_rate,
+            elitism,
+            tabu_tenure,
+            restart_after,
+            ants,
+            evaporation,
+            progress,
+            progress_interval,
             show_unsolved,
+            format,
+            alphabet,
+            algorithm,
+            portfolio,
+            cell_order,
+            value_order,
+            batch,
+            validate,
+            candidates,
+            count,
+            count_limit,
+            solutions,
+            dimacs,
         }
     }
 }
@@ -10,7 +10,7 @@ impl log::Log for SimpleLogger {
 
     fn log(&self, record: &Record) {
         if self.enabled(record.metadata()) {
-            println!("{}: {}", record.level(), record.args());
+            eprintln!("{}: {}", record.level(), record.args());
         }
     }
 
@@ -29,7 +29,7 @@ pub fn init(level: u8) {
     let log_level: LevelFilter = match level {
         0 => LevelFilter::Warn,
         1 => LevelFilter::Info,
-        2 | _ => LevelFilter::Trace,
+        _ => LevelFilter::Trace,
     };
     let _ = _init(log_level);
     debug!("Set logging level to: {}", log_level);
@@ -2,15 +2,28 @@
 extern crate clap;
 
 use log::{error, info};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write};
 use std::process;
+use std::time::{Duration, Instant};
 
+mod batch;
 mod cli;
 mod config;
 mod logger;
 mod sudoku;
 
 use config::Config;
-use sudoku::solver::{Backtracing, Montecarlo, Solver};
+use sudoku::solver::{
+    Aco, Backtracing, CancellationToken, Dlx, Genetic, Montecarlo, Observer, Outcome, Portfolio,
+    Propagation, Sat, Solver, SolverFactory, Tabu, Tempering,
+};
+use sudoku::{CandidateGrid, Cnf, Field, Format, Report, Sudoku, Uniqueness};
+
+/// Exit code used if the sudoku could not be read or parsed.
+const EXIT_PARSE_ERROR: i32 = 2;
+/// Exit code used if the solution could not be written.
+const EXIT_OUTPUT_ERROR: i32 = 3;
 
 fn main() {
     let parser = cli::configure_parser();
@@ -20,34 +33,373 @@ fn main() {
     logger::init(matches.occurrences_of("verbosity") as u8);
 
     let cfg = Config::from_matches(&matches);
-    let mut s = sudoku::Sudoku::new();
-    s.read(&cfg.input_file);
+    if cfg.batch {
+        solve_batch(&cfg);
+    }
 
-    let mut solver = match matches.value_of("algorithm") {
-        Some("backtracing") => Box::new(Backtracing::new(cfg.max_tries)) as Box<dyn Solver>,
-        Some("montecarlo") => Box::new(Montecarlo::new(cfg.max_tries, 0.15)) as Box<dyn Solver>,
-        _ => Box::new(Backtracing::new(cfg.max_tries)) as Box<dyn Solver>,
+    let empty = match &cfg.alphabet {
+        Some(alphabet) => Sudoku::with_alphabet(alphabet),
+        None => Ok(Sudoku::new()),
     };
+    let result = empty.and_then(|mut s| {
+        s.read(&cfg.input_file)?;
+        // The alphabet also applies to sudokus that are read from JSON.
+        if let Some(alphabet) = &cfg.alphabet {
+            s.grid.set_alphabet(alphabet.clone())?;
+        }
+        Ok(s)
+    });
+    let mut s = match result {
+        Ok(s) => s,
+        Err(err) => {
+            error!(
+                "Fatal. Failed to read sudoku from {}: {}",
+                cfg.input_file, err
+            );
+            process::exit(EXIT_PARSE_ERROR);
+        }
+    };
+    info!(
+        "Read sudoku of size {}x{} with parcels of {}x{} fields.",
+        s.grid.size(),
+        s.grid.size(),
+        s.grid.box_width(),
+        s.grid.box_height()
+    );
 
+    if cfg.validate {
+        validate(&cfg, &s);
+    }
+    if cfg.candidates {
+        print_candidates(&cfg, &s);
+    }
+    if cfg.count {
+        count_solutions(&cfg, &s);
+    }
+    if let Some(limit) = cfg.solutions {
+        print_solutions(&cfg, &s, limit);
+    }
+    if cfg.dimacs {
+        print_dimacs(&cfg, &s);
+    }
+
+    // The deadline of the solver is derived from its creation, so the time
+    // measurement starts before.
+    let start = Instant::now();
+    let mut solver = create_solver(&cfg);
     s = solver.solve(s);
+    let elapsed = start.elapsed();
+    if let Some(seed) = solver.get_seed() {
+        info!("Replay this run with --seed {}.", seed);
+    }
 
-    match solver.is_success() {
-        true => {
-            info!(
-                "Success. Solving the sudoku needed {} tries.",
-                solver.get_tries()
-            );
-            s.print(cfg.show_unsolved);
-            process::exit(0);
+    if solver.is_success() {
+        info!(
+            "Success. Solving the sudoku needed {} tries.",
+            solver.get_tries()
+        );
+        if let Some(winner) = solver.get_winner() {
+            info!("The portfolio was won by {}.", winner);
         }
-        false => {
-            error!(
-                "Fatal. Exceeded the limit of {} tries. \
-                Make sure that the sudoku is valid and consider increasing this \
-                number with the --max-tries argument.",
-                cfg.max_tries
+        write_solution(&cfg, &s, solver.as_ref(), elapsed);
+        process::exit(0);
+    }
+
+    match solver.get_outcome() {
+        Some(Outcome::Unsolvable) => error!("Fatal. The sudoku has no solution."),
+        // Only the timeout cancels the solver.
+        Some(Outcome::Cancelled) => error!(
+            "Fatal. Exceeded the timeout of {:?}. \
+            Consider increasing it with the --timeout argument.",
+            cfg.timeout.unwrap_or_default()
+        ),
+        _ => error!(
+            "Fatal. Exceeded the limit of {} tries. \
+            Make sure that the sudoku is valid and consider increasing this \
+            number with the --max-tries argument.",
+            cfg.max_tries
+        ),
+    }
+    // The JSON output contains the result of failed runs as well.
+    if cfg.format == Format::Json {
+        write_solution(&cfg, &s, solver.as_ref(), elapsed);
+    }
+    process::exit(1);
+}
+
+/// Writes the solution to the output selected with the --output argument.
+fn write_solution(cfg: &Config, s: &Sudoku, solver: &dyn Solver, elapsed: Duration) {
+    let mut out = open_output(cfg);
+    let result = match cfg.format {
+        Format::Json => {
+            let mut report = Report::from_run(
+                s,
+                solver.is_success(),
+                &cfg.algorithm,
+                solver.get_tries(),
+                elapsed,
             );
-            process::exit(1);
+            report.winner = solver.get_winner().map(String::from);
+            report.seed = solver.get_seed();
+            serde_json::to_writer(&mut out, &report)
+                .map_err(io::Error::from)
+                .and_then(|_| writeln!(out))
+        }
+        _ => s.write_to(&mut out, cfg.show_unsolved, cfg.format),
+    };
+    if let Err(err) = result.and_then(|_| out.flush()) {
+        error!("Fatal. Failed to write solution: {}", err);
+        process::exit(EXIT_OUTPUT_ERROR);
+    }
+}
+
+/// Prints the sudoku with all conflicting fields highlighted followed by the
+/// list of conflicts and exits afterwards. Fields are only highlighted when
+/// printing to a terminal.
+fn validate(cfg: &Config, s: &Sudoku) -> ! {
+    let conflicts = s.get_conflicts();
+    let mut out = open_output(cfg);
+    let result = match cfg.format {
+        Format::Json => {
+            let report = Report::from_validation(s, conflicts.clone());
+            serde_json::to_writer(&mut out, &report)
+                .map_err(io::Error::from)
+                .and_then(|_| writeln!(out))
         }
+        _ => {
+            let grid = if cfg.format == Format::Boxed
+                && cfg.output_file.is_none()
+                && io::stdout().is_terminal()
+            {
+                let fields: Vec<Field> = conflicts
+                    .iter()
+                    .flat_map(|c| vec![c.first.clone(), c.second.clone()])
+                    .collect();
+                s.grid.fmt_highlighted(&fields)
+            } else {
+                s.grid.fmt_as(cfg.format)
+            };
+            writeln!(out, "{}", grid).and_then(|_| {
+                conflicts
+                    .iter()
+                    .try_for_each(|conflict| writeln!(out, "{}", conflict))
+            })
+        }
+    };
+    if let Err(err) = result.and_then(|_| out.flush()) {
+        error!("Fatal. Failed to write conflicts: {}", err);
+        process::exit(EXIT_OUTPUT_ERROR);
+    }
+
+    if conflicts.is_empty() {
+        info!("The sudoku is valid.");
+        process::exit(0);
+    }
+    error!("Fatal. Found {} conflicts.", conflicts.len());
+    process::exit(1);
+}
+
+/// Prints the pencil marks of the sudoku and exits afterwards.
+fn print_candidates(cfg: &Config, s: &Sudoku) -> ! {
+    let candidates = CandidateGrid::from_grid(&s.grid);
+    let mut out = open_output(cfg);
+    if let Err(err) = writeln!(out, "{}", candidates.fmt()).and_then(|_| out.flush()) {
+        error!("Fatal. Failed to write candidates: {}", err);
+        process::exit(EXIT_OUTPUT_ERROR);
+    }
+    process::exit(0);
+}
+
+/// Prints the number of solutions of the sudoku up to the limit given with
+/// the --count-limit argument and whether the solution is unique. Exits with
+/// 0 if the solution is unique and with 1 otherwise.
+fn count_solutions(cfg: &Config, s: &Sudoku) -> ! {
+    let count = s.count_solutions(cfg.count_limit);
+    let uniqueness = Uniqueness::from_count(count);
+    let mut out = open_output(cfg);
+    let result = match cfg.format {
+        Format::Json => serde_json::to_writer(&mut out, &Report::from_count(s, count))
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(out)),
+        _ if uniqueness != Uniqueness::Multiple => writeln!(out, "{}", uniqueness),
+        _ if count >= cfg.count_limit => {
+            writeln!(out, "{} (found at least {} solutions)", uniqueness, count)
+        }
+        _ => writeln!(out, "{} (found {} solutions)", uniqueness, count),
+    };
+    if let Err(err) = result.and_then(|_| out.flush()) {
+        error!("Fatal. Failed to write solution count: {}", err);
+        process::exit(EXIT_OUTPUT_ERROR);
+    }
+    process::exit(if uniqueness == Uniqueness::Unique {
+        0
+    } else {
+        1
+    });
+}
+
+/// Prints the first solutions of the sudoku up to the given limit and exits
+/// afterwards.
+fn print_solutions(cfg: &Config, s: &Sudoku, limit: usize) -> ! {
+    let mut out = open_output(cfg);
+    let mut count = 0;
+    for grid in s.solutions().take(limit) {
+        // Separate boxed solutions by an empty line, like in batch mode.
+        let separator = match cfg.format {
+            Format::Boxed if count > 0 => writeln!(out),
+            _ => Ok(()),
+        };
+        let solution = Sudoku { grid };
+        let result =
+            separator.and_then(|_| solution.write_to(&mut out, cfg.show_unsolved, cfg.format));
+        if let Err(err) = result {
+            error!("Fatal. Failed to write solution: {}", err);
+            process::exit(EXIT_OUTPUT_ERROR);
+        }
+        count += 1;
+    }
+    if let Err(err) = out.flush() {
+        error!("Fatal. Failed to write solution: {}", err);
+        process::exit(EXIT_OUTPUT_ERROR);
+    }
+
+    if count == 0 {
+        error!("Fatal. The sudoku has no solution.");
+        process::exit(1);
+    }
+    info!("Printed {} solutions.", count);
+    process::exit(0);
+}
+
+/// Prints the sudoku in the DIMACS CNF format and exits afterwards.
+fn print_dimacs(cfg: &Config, s: &Sudoku) -> ! {
+    let mut out = open_output(cfg);
+    let result = Cnf::from_sudoku(s).write_dimacs(&mut out);
+    if let Err(err) = result.and_then(|_| out.flush()) {
+        error!("Fatal. Failed to write formula: {}", err);
+        process::exit(EXIT_OUTPUT_ERROR);
+    }
+    process::exit(0);
+}
+
+/// Creates the solver that has been selected with the --algorithm argument.
+/// The time given with the --timeout argument starts to run immediately.
+/// The progress is printed to stderr if requested with --progress.
+fn create_solver(cfg: &Config) -> Box<dyn Solver> {
+    let mut solver = create_algorithm(&cfg.algorithm, cfg);
+    if cfg.progress {
+        solver.set_observer(Observer::new(cfg.progress_interval, |progress| {
+            eprintln!("Progress. {}", progress)
+        }));
+    }
+    if let Some(timeout) = cfg.timeout {
+        solver.set_cancellation_token(CancellationToken::with_deadline(Instant::now() + timeout));
+    }
+    solver
+}
+
+/// Creates the solver for an algorithm by its name. The portfolio runs the
+/// algorithms given with the --portfolio argument.
+fn create_algorithm(algorithm: &str, cfg: &Config) -> Box<dyn Solver> {
+    match algorithm {
+        "portfolio" => Box::new(Portfolio::new(
+            cfg.portfolio
+                .iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    let factory: SolverFactory = {
+                        let name = name.clone();
+                        let mut cfg = cfg.clone();
+                        // Every solver of the portfolio gets its own seed.
+                        cfg.seed = cfg.seed.map(|seed| seed.wrapping_add(i as u64));
+                        Box::new(move || create_algorithm(&name, &cfg))
+                    };
+                    (name.clone(), factory)
+                })
+                .collect(),
+        )),
+        "montecarlo" => Box::new(Montecarlo::new(cfg.max_tries, cfg.schedule, cfg.seed)),
+        "dlx" => Box::new(Dlx::new(cfg.max_tries)),
+        "sat" => Box::new(Sat::new(cfg.max_tries)),
+        "genetic" => Box::new(Genetic::new(
+            cfg.max_tries,
+            cfg.population,
+            cfg.mutation_rate,
+            cfg.elitism,
+            cfg.seed,
+        )),
+        "tabu" => Box::new(Tabu::new(
+            cfg.max_tries,
+            cfg.tabu_tenure,
+            cfg.restart_after,
+            cfg.seed,
+        )),
+        "aco" => Box::new(Aco::new(cfg.max_tries, cfg.ants, cfg.evaporation, cfg.seed)),
+        "tempering" => Box::new(Tempering::new(
+            cfg.max_tries,
+            Tempering::ladder(
+                cfg.schedule.end_temperature,
+                cfg.schedule.start_temperature,
+                cfg.replicas,
+            ),
+            cfg.exchange_interval,
+            cfg.seed,
+        )),
+        "propagation" => Box::new(Propagation::new(
+            cfg.max_tries,
+            cfg.cell_order,
+            cfg.value_order,
+        )),
+        _ => Box::new(Backtracing::new(cfg.max_tries)),
+    }
+}
+
+/// Solves all sudokus of the input file, prints one solution per line and
+/// exits afterwards.
+fn solve_batch(cfg: &Config) -> ! {
+    let input: Box<dyn BufRead> = match cfg.input_file.as_str() {
+        "-" => Box::new(io::stdin().lock()),
+        file => match File::open(file) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(err) => {
+                error!(
+                    "Fatal. Failed to read sudokus from {}: {}",
+                    cfg.input_file, err
+                );
+                process::exit(EXIT_PARSE_ERROR);
+            }
+        },
+    };
+    let mut out = open_output(cfg);
+    match batch::run(
+        input,
+        &mut out,
+        cfg.format,
+        &cfg.algorithm,
+        cfg.alphabet.as_ref(),
+        || create_solver(cfg),
+    ) {
+        Ok(summary) => {
+            eprintln!("{}", summary);
+            process::exit(if summary.failed == 0 { 0 } else { 1 });
+        }
+        Err(err) => {
+            error!("Fatal. Batch run aborted: {}", err);
+            process::exit(EXIT_PARSE_ERROR);
+        }
+    }
+}
+
+/// Opens the file given with the --output argument or stdout otherwise.
+fn open_output(cfg: &Config) -> Box<dyn Write> {
+    match &cfg.output_file {
+        Some(file) => match File::create(file) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(err) => {
+                error!("Fatal. Failed to open output file {}: {}", file, err);
+                process::exit(EXIT_OUTPUT_ERROR);
+            }
+        },
+        None => Box::new(io::stdout()),
     }
 }
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+use super::error::ParseError;
+
+/// Symbols of the default alphabets, the first n symbols are used for a
+/// sudoku of size n.
+const DEFAULT_SYMBOLS: &str = "123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Characters that may mark an empty field, in order of preference.
+const BLANK_SYMBOLS: [char; 3] = ['x', '.', '0'];
+
+/// Characters that have a special meaning in the supported formats.
+const RESERVED_SYMBOLS: [char; 5] = ['|', '-', '#', '{', '}'];
+
+/// The symbols that represent the values 1, 2, ... n of the fields of a
+/// sudoku of size n, e.g. `123456789` for classic sudokus or `0123456789ABCDEF`
+/// for 16x16 sudokus.
+///
+/// Empty fields are marked with `x`, `.` or `0`, unless the symbol is part of
+/// the alphabet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Alphabet {
+    symbols: Vec<char>,
+}
+
+impl fmt::Display for Alphabet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbols.iter().collect::<String>())
+    }
+}
+
+impl From<Alphabet> for String {
+    fn from(alphabet: Alphabet) -> String {
+        alphabet.to_string()
+    }
+}
+
+impl TryFrom<String> for Alphabet {
+    type Error = ParseError;
+
+    fn try_from(symbols: String) -> Result<Alphabet, ParseError> {
+        Alphabet::new(&symbols)
+    }
+}
+
+impl Alphabet {
+    /// Creates an alphabet from a list of symbols.
+    /// The symbols must be unique and must neither be whitespace nor one of
+    /// the characters `|`, `-`, `#`, `{` and `}`. At least one of `x`, `.`
+    /// and `0` must be left to mark empty fields.
+    pub fn new(symbols: &str) -> Result<Alphabet, ParseError> {
+        let symbols: Vec<char> = symbols.chars().collect();
+        let is_invalid = |(i, c): (usize, &char)| {
+            c.is_whitespace() || RESERVED_SYMBOLS.contains(c) || symbols[..i].contains(c)
+        };
+        if symbols.is_empty() || symbols.iter().enumerate().any(is_invalid) {
+            return Err(ParseError::InvalidAlphabet {
+                symbols: symbols.into_iter().collect(),
+            });
+        }
+        let alphabet = Alphabet { symbols };
+        if !BLANK_SYMBOLS.iter().any(|c| alphabet.is_blank_free(c)) {
+            return Err(ParseError::NoBlankSymbol {
+                symbols: alphabet.to_string(),
+            });
+        }
+        Ok(alphabet)
+    }
+
+    /// Creates an alphabet from the name of a predefined alphabet (`digits`,
+    /// `hex` or `letters`) or from a list of symbols otherwise.
+    pub fn from_name(name: &str) -> Result<Alphabet, ParseError> {
+        match name {
+            "digits" => Alphabet::new("123456789"),
+            "hex" => Alphabet::new("0123456789ABCDEF"),
+            "letters" => Alphabet::new("ABCDEFGHIJKLMNOPQRSTUVWXY"),
+            _ => Alphabet::new(name),
+        }
+    }
+
+    /// Returns the default alphabet for a sudoku of the given size, which
+    /// consists of the digits 1 - 9 followed by the letters `A`, `B`, ...
+    /// Sizes above `DEFAULT_SYMBOLS` are not supported.
+    pub fn default_for(size: usize) -> Alphabet {
+        Alphabet {
+            symbols: DEFAULT_SYMBOLS.chars().take(size).collect(),
+        }
+    }
+
+    /// Returns the number of symbols, which equals the size of the sudoku.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Returns the symbol that represents a value or the blank symbol for 0.
+    pub fn symbol_of(&self, value: u8, blank: char) -> char {
+        match value {
+            0 => blank,
+            _ => self.symbols[usize::from(value) - 1],
+        }
+    }
+
+    /// Returns the value that is represented by a symbol or 0 for symbols
+    /// that mark empty fields. Letters are matched case-insensitively if
+    /// there is no exact match.
+    pub fn value_of(&self, symbol: char) -> Option<u8> {
+        self.symbols
+            .iter()
+            .position(|c| *c == symbol)
+            .or_else(|| {
+                self.symbols
+                    .iter()
+                    .position(|c| c.eq_ignore_ascii_case(&symbol))
+            })
+            .map(|i| i as u8 + 1)
+            .or_else(|| match BLANK_SYMBOLS.contains(&symbol) {
+                true => Some(0),
+                false => None,
+            })
+    }
+
+    /// Returns the symbol that marks empty fields. This is `preferred` unless
+    /// it is part of the alphabet, in which case the first free symbol of
+    /// `x`, `.` and `0` is used. `Alphabet::new` makes sure that there is one.
+    pub fn blank(&self, preferred: char) -> char {
+        std::iter::once(preferred)
+            .chain(BLANK_SYMBOLS.iter().copied())
+            .find(|c| self.is_blank_free(c))
+            .unwrap_or(preferred)
+    }
+
+    /// Returns whether a symbol can mark empty fields, i.e. whether it is not
+    /// part of the alphabet.
+    fn is_blank_free(&self, symbol: &char) -> bool {
+        !self.symbols.iter().any(|s| s.eq_ignore_ascii_case(symbol))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_map_symbols() {
+        let alphabet = Alphabet::default_for(16);
+        assert_eq!(alphabet.symbol_of(1, 'x'), '1');
+        assert_eq!(alphabet.symbol_of(16, 'x'), 'G');
+        assert_eq!(alphabet.symbol_of(0, 'x'), 'x');
+        assert_eq!(alphabet.value_of('9'), Some(9));
+        assert_eq!(alphabet.value_of('a'), Some(10));
+        assert_eq!(alphabet.value_of('0'), Some(0));
+        assert_eq!(alphabet.value_of('H'), None);
+        assert_eq!(alphabet.value_of('-'), None);
+    }
+
+    #[test]
+    fn it_should_keep_blank_symbols_apart() {
+        let hex = Alphabet::from_name("hex").unwrap();
+        assert_eq!(hex.value_of('0'), Some(1));
+        assert_eq!(hex.value_of('F'), Some(16));
+        assert_eq!(hex.value_of('.'), Some(0));
+        assert_eq!(hex.blank('.'), '.');
+
+        let wordoku = Alphabet::new("EXPLAINED").unwrap_err();
+        assert_eq!(
+            format!("{}", wordoku),
+            "invalid alphabet 'EXPLAINED': symbols must be unique and must not contain \
+            whitespace, '|', '-', '#', '{' or '}'"
+        );
+        let wordoku = Alphabet::new("XYLOPHMUS").unwrap();
+        assert_eq!(wordoku.value_of('x'), Some(1));
+        assert_eq!(wordoku.blank('x'), '.');
+    }
+
+    #[test]
+    fn it_should_reject_reserved_symbols() {
+        assert!(Alphabet::new("12345678|").is_err());
+        assert!(Alphabet::new("").is_err());
+        assert!(Alphabet::new("1234 5678").is_err());
+    }
+
+    #[test]
+    fn it_should_leave_a_blank_symbol() {
+        let err = Alphabet::new("X.0_").unwrap_err();
+        assert_eq!(
+            format!("{}", err),
+            "invalid alphabet 'X.0_': at least one of 'x', '.' and '0' must be left to \
+            mark empty fields"
+        );
+        let alphabet = Alphabet::new("x.AB").unwrap();
+        assert_eq!(alphabet.blank('x'), '0');
+        assert_eq!(alphabet.value_of('0'), Some(0));
+    }
+}
@@ -0,0 +1,207 @@
+use super::field::Field;
+use super::grid::Grid;
+use super::value_set::ValueSet;
+
+/// The pencil marks of a sudoku, i.e. the values that are still possible for
+/// every field, next to the values that have already been placed.
+/// Placing a value removes it from the candidates of all peers, so the
+/// candidates of a grid derived with `from_grid` never conflict with the
+/// placed values.
+#[derive(Debug, Clone)]
+pub struct CandidateGrid {
+    grid: Grid,
+    /// The candidates of all fields in row major order. Fields with a value
+    /// have this value as their only candidate.
+    candidates: Vec<ValueSet>,
+}
+
+impl CandidateGrid {
+    /// Derives the candidates of every empty field from the values in its
+    /// row, column and parcel.
+    pub fn from_grid(grid: &Grid) -> CandidateGrid {
+        let size = grid.size();
+        let mut candidates = vec![];
+        for r in 0..size {
+            for c in 0..size {
+                let field = Field::new(r, c);
+                candidates.push(match grid.get(&field) {
+                    0 => grid.get_candidates(&field),
+                    value => Some(value).into_iter().collect(),
+                });
+            }
+        }
+        CandidateGrid {
+            grid: grid.clone(),
+            candidates,
+        }
+    }
+
+    /// Creates a candidate grid from the placed values and the candidates of
+    /// all fields in row major order, without deriving anything.
+    pub fn from_parts(grid: Grid, candidates: Vec<ValueSet>) -> CandidateGrid {
+        CandidateGrid { grid, candidates }
+    }
+
+    /// Returns the placed values.
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Returns the candidates of a field.
+    pub fn get(&self, field: &Field) -> ValueSet {
+        self.candidates[self.index(field)]
+    }
+
+    /// Removes a candidate from an empty field. Returns `false` if it was not
+    /// a candidate.
+    pub fn eliminate(&mut self, field: &Field, value: u8) -> bool {
+        let index = self.index(field);
+        if self.grid.get(field) != 0 || !self.candidates[index].contains(value) {
+            return false;
+        }
+        self.candidates[index].remove(value);
+        true
+    }
+
+    /// Places a value in a field and removes it from the candidates of all
+    /// empty fields in the same row, column and parcel.
+    pub fn place(&mut self, field: &Field, value: u8) {
+        self.grid.set(field, value);
+        let index = self.index(field);
+        self.candidates[index] = Some(value).into_iter().collect();
+        for peer in self.grid.get_peers(field).iter() {
+            self.eliminate(peer, value);
+        }
+    }
+
+    /// Formats the pencil marks. Every field is drawn as a block with the
+    /// dimensions of a parcel, e.g. 3x3 characters for a 9x9 sudoku, which
+    /// shows each candidate `v` at the `v`-th position and `.` otherwise.
+    /// The blocks of placed values are filled with the value.
+    /// Fields are separated by spaces and empty lines, parcels by `|` and `-`
+    /// like in the boxed format:
+    /// ```text
+    /// 1.. .2. ..3|... ... ...|...
+    /// ... ... ...|4.. .5. ..6|...
+    /// ... ... ...|... ... ...|...
+    ///
+    /// ...
+    /// ```
+    pub fn fmt(&self) -> String {
+        let size = self.grid.size();
+        let box_width = self.grid.box_width();
+        let box_height = self.grid.box_height();
+        let alphabet = self.grid.alphabet();
+        let blank = alphabet.blank('.');
+        let width = usize::from(size) * usize::from(box_width + 1) - 1;
+        let mut lines: Vec<String> = vec![];
+        for r in 0..size {
+            if r > 0 && r % box_height == 0 {
+                lines.push("-".repeat(width));
+            } else if r > 0 {
+                lines.push(String::new());
+            }
+            for mark_row in 0..box_height {
+                let mut line = String::new();
+                for c in 0..size {
+                    if c > 0 {
+                        line.push(if c % box_width == 0 { '|' } else { ' ' });
+                    }
+                    let field = Field::new(r, c);
+                    let value = self.grid.get(&field);
+                    let candidates = self.get(&field);
+                    for mark_col in 0..box_width {
+                        let mark = mark_row * box_width + mark_col + 1;
+                        line.push(match value {
+                            0 if candidates.contains(mark) => alphabet.symbol_of(mark, blank),
+                            0 => blank,
+                            _ => alphabet.symbol_of(value, blank),
+                        });
+                    }
+                }
+                lines.push(line);
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn index(&self, field: &Field) -> usize {
+        usize::from(field.row) * usize::from(self.grid.size()) + usize::from(field.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_grid() -> Grid {
+        // 1.3.
+        // ..4.
+        // ....
+        // ...3
+        let mut grid = Grid::new(vec![vec![0; 4]; 4], 2, 2);
+        grid.set(&Field::new(0, 0), 1);
+        grid.set(&Field::new(0, 2), 3);
+        grid.set(&Field::new(1, 2), 4);
+        grid.set(&Field::new(3, 3), 3);
+        grid
+    }
+
+    #[test]
+    fn it_should_derive_candidates() {
+        let candidates = CandidateGrid::from_grid(&small_grid());
+        assert_eq!(
+            candidates
+                .get(&Field::new(0, 1))
+                .iter()
+                .collect::<Vec<u8>>(),
+            vec![2, 4]
+        );
+        assert_eq!(
+            candidates
+                .get(&Field::new(0, 0))
+                .iter()
+                .collect::<Vec<u8>>(),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn it_should_update_peers() {
+        let mut candidates = CandidateGrid::from_grid(&small_grid());
+        candidates.place(&Field::new(0, 1), 2);
+        assert!(candidates.get(&Field::new(0, 3)).is_empty());
+        assert_eq!(
+            candidates
+                .get(&Field::new(1, 0))
+                .iter()
+                .collect::<Vec<u8>>(),
+            vec![3]
+        );
+        assert!(!candidates.get(&Field::new(2, 1)).contains(2));
+        assert!(candidates.get(&Field::new(2, 0)).contains(2));
+
+        assert!(candidates.eliminate(&Field::new(2, 0), 2));
+        assert!(!candidates.eliminate(&Field::new(2, 0), 2));
+        assert!(!candidates.eliminate(&Field::new(0, 0), 1));
+    }
+
+    #[test]
+    fn it_should_format_pencil_marks() {
+        let candidates = CandidateGrid::from_grid(&small_grid());
+        assert_eq!(
+            candidates.fmt(),
+            "11 .2|33 .2\n\
+             11 .4|33 ..\n\
+             \n\
+             .2 .2|44 12\n\
+             3. 3.|44 ..\n\
+             -----------\n\
+             .2 12|12 12\n\
+             34 34|.. .4\n\
+             \n\
+             .2 12|12 33\n\
+             .4 .4|.. 33"
+        );
+    }
+}
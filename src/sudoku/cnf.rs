@@ -0,0 +1,136 @@
+use std::io::{self, Write};
+
+use super::field::Field;
+use super::unit::Unit;
+use super::Sudoku;
+
+/// A sudoku encoded as a boolean formula in conjunctive normal form.
+/// The variable `(row * n + column) * n + value` (1-based, see `variable`)
+/// is true if the field in `row` and `column` has the `value`. Clauses are
+/// lists of literals, i.e. variables that are negated if they are negative,
+/// like in the DIMACS format.
+#[derive(Debug, Clone)]
+pub struct Cnf {
+    size: u8,
+    pub clauses: Vec<Vec<i32>>,
+}
+
+impl Cnf {
+    /// Encodes the constraints of a sudoku: every field has exactly one
+    /// value, every row, column and parcel contains every value exactly once
+    /// and the given values are set.
+    pub fn from_sudoku(sudoku: &Sudoku) -> Cnf {
+        let grid = &sudoku.grid;
+        let size = grid.size();
+        let mut cnf = Cnf {
+            size,
+            clauses: vec![],
+        };
+
+        for r in 0..size {
+            for c in 0..size {
+                let field = Field::new(r, c);
+                let literals: Vec<i32> = (1..=size).map(|v| cnf.variable(&field, v)).collect();
+                cnf.add_exactly_one(&literals);
+            }
+        }
+        for unit in Unit::all(size) {
+            let fields = grid.get_unit_fields(&unit);
+            for value in 1..=size {
+                let literals: Vec<i32> = fields.iter().map(|f| cnf.variable(f, value)).collect();
+                cnf.add_exactly_one(&literals);
+            }
+        }
+        for r in 0..size {
+            for c in 0..size {
+                let field = Field::new(r, c);
+                let value = grid.get(&field);
+                if value != 0 {
+                    cnf.clauses.push(vec![cnf.variable(&field, value)]);
+                }
+            }
+        }
+        cnf
+    }
+
+    /// Returns the variable that is true if the field has the value.
+    pub fn variable(&self, field: &Field, value: u8) -> i32 {
+        let n = i32::from(self.size);
+        (i32::from(field.row) * n + i32::from(field.column)) * n + i32::from(value)
+    }
+
+    /// Returns the number of variables.
+    pub fn variable_count(&self) -> usize {
+        usize::from(self.size).pow(3)
+    }
+
+    /// Requires exactly one of the literals to be true, which is encoded as
+    /// one clause with all literals and one clause per pair that forbids both.
+    fn add_exactly_one(&mut self, literals: &[i32]) {
+        self.clauses.push(literals.to_vec());
+        for (i, a) in literals.iter().enumerate() {
+            for b in literals[i + 1..].iter() {
+                self.clauses.push(vec![-a, -b]);
+            }
+        }
+    }
+
+    /// Writes the formula in the DIMACS CNF format that is understood by
+    /// most SAT solvers. The comment lines explain the variables.
+    pub fn write_dimacs<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let n = self.size;
+        writeln!(out, "c sudoku of size {}x{}", n, n)?;
+        writeln!(
+            out,
+            "c variable (row * {} + column) * {} + value is true if the field has the value",
+            n, n
+        )?;
+        writeln!(out, "c rows and columns start at 0, values at 1")?;
+        writeln!(
+            out,
+            "p cnf {} {}",
+            self.variable_count(),
+            self.clauses.len()
+        )?;
+        for clause in self.clauses.iter() {
+            for literal in clause.iter() {
+                write!(out, "{} ", literal)?;
+            }
+            writeln!(out, "0")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_encode_constraints() {
+        let s: Sudoku = "1.3..4.......3..".parse().unwrap();
+        let cnf = Cnf::from_sudoku(&s);
+        assert_eq!(cnf.variable_count(), 64);
+        assert_eq!(cnf.variable(&Field::new(0, 0), 1), 1);
+        assert_eq!(cnf.variable(&Field::new(3, 3), 4), 64);
+        // 16 fields and 12 units with 4 values each, with 1 + 6 clauses for
+        // each, plus 4 given values.
+        assert_eq!(cnf.clauses.len(), (16 + 12 * 4) * 7 + 4);
+        assert!(cnf
+            .clauses
+            .contains(&vec![cnf.variable(&Field::new(1, 1), 4)]));
+    }
+
+    #[test]
+    fn it_should_write_dimacs() {
+        let s: Sudoku = "1.3..4.......3..".parse().unwrap();
+        let mut out: Vec<u8> = vec![];
+        Cnf::from_sudoku(&s).write_dimacs(&mut out).unwrap();
+        let dimacs = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = dimacs.lines().filter(|l| !l.starts_with('c')).collect();
+        assert_eq!(lines[0], "p cnf 64 452");
+        assert_eq!(lines[1], "1 2 3 4 0");
+        assert_eq!(lines[2], "-1 -2 0");
+        assert_eq!(lines.len(), 453);
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::field::Field;
+use super::unit::Unit;
+
+/// Two fields of the same unit that contain the same value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conflict {
+    pub unit: Unit,
+    pub value: u8,
+    pub first: Field,
+    pub second: Field,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} and {} in {} contain the same value",
+            self.first, self.second, self.unit
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_format() {
+        let conflict = Conflict {
+            unit: Unit::Column(2),
+            value: 5,
+            first: Field::new(0, 2),
+            second: Field::new(7, 2),
+        };
+        assert_eq!(
+            format!("{}", conflict),
+            "(0,2) and (7,2) in column 2 contain the same value"
+        );
+    }
+}
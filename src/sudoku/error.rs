@@ -0,0 +1,166 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Errors that can occur while reading a sudoku.
+/// Line and column numbers are 1-based and refer to the raw input, including
+/// comment lines and grid separators.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The input could not be read at all.
+    Io(io::Error),
+    /// A character that is neither a digit nor a placeholder for an empty field.
+    InvalidCharacter {
+        line: usize,
+        column: usize,
+        character: char,
+    },
+    /// A row does not contain the expected number of fields.
+    /// For JSON input `line` refers to the row.
+    ColumnCount {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The sudoku does not contain the expected number of rows.
+    RowCount { expected: usize, found: usize },
+    /// The number of fields in a line does not match any sudoku size.
+    FieldCount { line: usize, found: usize },
+    /// Sudokus of this size are not supported.
+    UnsupportedSize { size: usize },
+    /// The parcels do not tile the sudoku.
+    BoxSize {
+        box_width: usize,
+        box_height: usize,
+        size: usize,
+    },
+    /// The symbols of an alphabet are not unique or contain reserved
+    /// characters.
+    InvalidAlphabet { symbols: String },
+    /// The alphabet uses every symbol that marks empty fields.
+    NoBlankSymbol { symbols: String },
+    /// The alphabet does not have one symbol per value.
+    AlphabetSize { expected: usize, found: usize },
+    /// A numeric field value that is out of range.
+    InvalidValue {
+        row: usize,
+        column: usize,
+        value: u8,
+    },
+    /// The input is not valid JSON or does not have the expected shape.
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(err) => write!(f, "could not read sudoku: {}", err),
+            ParseError::InvalidCharacter {
+                line,
+                column,
+                character,
+            } => write!(
+                f,
+                "invalid character '{}' in line {}, column {}",
+                character, line, column
+            ),
+            ParseError::ColumnCount {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected {} fields in line {}, but found {}",
+                expected, line, found
+            ),
+            ParseError::RowCount { expected, found } => {
+                write!(f, "expected {} rows, but found {}", expected, found)
+            }
+            ParseError::FieldCount { line, found } => write!(
+                f,
+                "found {} fields in line {}, which does not match any sudoku size",
+                found, line
+            ),
+            ParseError::UnsupportedSize { size } => {
+                write!(f, "sudokus of size {}x{} are not supported", size, size)
+            }
+            ParseError::BoxSize {
+                box_width,
+                box_height,
+                size,
+            } => write!(
+                f,
+                "parcels of {}x{} fields do not fit a sudoku of size {}x{}",
+                box_width, box_height, size, size
+            ),
+            ParseError::InvalidAlphabet { symbols } => write!(
+                f,
+                "invalid alphabet '{}': symbols must be unique and must not contain \
+                whitespace, '|', '-', '#', '{{' or '}}'",
+                symbols
+            ),
+            ParseError::NoBlankSymbol { symbols } => write!(
+                f,
+                "invalid alphabet '{}': at least one of 'x', '.' and '0' must be left to \
+                mark empty fields",
+                symbols
+            ),
+            ParseError::AlphabetSize { expected, found } => write!(
+                f,
+                "expected an alphabet of {} symbols, but found {}",
+                expected, found
+            ),
+            ParseError::InvalidValue { row, column, value } => write!(
+                f,
+                "invalid value {} in row {}, column {}",
+                value, row, column
+            ),
+            ParseError::Json(err) => write!(f, "invalid JSON: {}", err),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io(err) => Some(err),
+            ParseError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(err: io::Error) -> ParseError {
+        ParseError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ParseError {
+    fn from(err: serde_json::Error) -> ParseError {
+        ParseError::Json(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_format() {
+        let err = ParseError::InvalidCharacter {
+            line: 3,
+            column: 5,
+            character: 'a',
+        };
+        assert_eq!(
+            format!("{}", err),
+            "invalid character 'a' in line 3, column 5"
+        );
+        let err = ParseError::RowCount {
+            expected: 9,
+            found: 8,
+        };
+        assert_eq!(format!("{}", err), "expected 9 rows, but found 8");
+    }
+}
@@ -1,28 +1,211 @@
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+use super::alphabet::Alphabet;
+use super::common;
+use super::error::ParseError;
 use super::field::Field;
-use std::iter;
+use super::unit::Unit;
+use super::value_set::ValueSet;
+
+/// Textual representations of a grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// Rows separated by newlines with parcel borders drawn as `|` and `-`.
+    Boxed,
+    /// All fields in a single line, empty fields are shown as `.` (or `x`).
+    Line,
+    /// A JSON object with the `box_width`, `box_height` and `alphabet` of
+    /// the grid and its `fields` as an array of rows, empty fields are set
+    /// to 0.
+    Json,
+}
 
-#[derive(Debug)]
+/// A grid is serialized together with the dimensions of its parcels and its
+/// alphabet. The mutable fields are derived from the empty fields when the
+/// grid is deserialized.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(into = "GridData", try_from = "GridData")]
 pub struct Grid {
     fields: Vec<Vec<u8>>,
+    box_width: u8,
+    box_height: u8,
+    alphabet: Alphabet,
+    used_values: UsedValues,
     pub mutable_fields: Vec<Field>,
 }
 
+/// Keeps track of the values that are used in each row, column and parcel,
+/// which is updated incrementally whenever a field is set.
+/// Besides a set per unit the number of occurrences of each value is counted,
+/// so that the sets stay correct while units contain duplicates, e.g. while
+/// the Montecarlo solver fills the parcels.
+#[derive(Debug, Clone)]
+struct UsedValues {
+    size: usize,
+    /// Sets of the rows, followed by those of the columns and parcels.
+    sets: Vec<ValueSet>,
+    /// `size` counters per unit, in the same order as `sets`.
+    counts: Vec<u8>,
+}
+
+impl UsedValues {
+    fn new(size: usize) -> UsedValues {
+        UsedValues {
+            size,
+            sets: vec![ValueSet::default(); 3 * size],
+            counts: vec![0; 3 * size * size],
+        }
+    }
+
+    fn index(&self, unit: &Unit) -> usize {
+        match *unit {
+            Unit::Row(index) => usize::from(index),
+            Unit::Column(index) => self.size + usize::from(index),
+            Unit::Parcel(index) => 2 * self.size + usize::from(index),
+        }
+    }
+
+    fn count(&self, unit: &Unit, value: u8) -> u8 {
+        self.counts[self.index(unit) * self.size + usize::from(value) - 1]
+    }
+
+    fn add(&mut self, unit: &Unit, value: u8) {
+        let index = self.index(unit);
+        self.counts[index * self.size + usize::from(value) - 1] += 1;
+        self.sets[index].insert(value);
+    }
+
+    fn remove(&mut self, unit: &Unit, value: u8) {
+        let index = self.index(unit);
+        let count = &mut self.counts[index * self.size + usize::from(value) - 1];
+        *count -= 1;
+        if *count == 0 {
+            self.sets[index].remove(value);
+        }
+    }
+}
+
+/// Serialized representation of a grid. Plain arrays of rows are accepted as
+/// well, in which case the default parcel dimensions and alphabet are used.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum GridData {
+    Boxed {
+        box_width: u8,
+        box_height: u8,
+        #[serde(default)]
+        alphabet: Option<Alphabet>,
+        fields: Vec<Vec<u8>>,
+    },
+    Rows(Vec<Vec<u8>>),
+}
+
 impl std::clone::Clone for Grid {
     fn clone(&self) -> Grid {
         Grid {
             fields: self.fields.clone(),
+            box_width: self.box_width,
+            box_height: self.box_height,
+            alphabet: self.alphabet.clone(),
+            used_values: self.used_values.clone(),
             mutable_fields: self.mutable_fields.clone(),
         }
     }
 }
 
+impl From<Grid> for GridData {
+    fn from(grid: Grid) -> GridData {
+        GridData::Boxed {
+            box_width: grid.box_width,
+            box_height: grid.box_height,
+            alphabet: Some(grid.alphabet),
+            fields: grid.fields,
+        }
+    }
+}
+
+impl TryFrom<GridData> for Grid {
+    type Error = ParseError;
+
+    /// Creates a grid from its rows and makes sure that it has the correct
+    /// dimensions and only contains valid values.
+    /// Errors refer to rows as lines.
+    fn try_from(data: GridData) -> Result<Grid, ParseError> {
+        let (fields, box_size, alphabet) = match data {
+            GridData::Boxed {
+                box_width,
+                box_height,
+                alphabet,
+                fields,
+            } => (fields, Some((box_width, box_height)), alphabet),
+            GridData::Rows(fields) => (fields, None, None),
+        };
+        let size = fields.len();
+        let (box_width, box_height) = match box_size {
+            Some(box_size) => box_size,
+            None => Grid::default_box_size(size).ok_or(ParseError::UnsupportedSize { size })?,
+        };
+        Grid::check_box_size(size, box_width, box_height)?;
+
+        for (row_index, row) in fields.iter().enumerate() {
+            if row.len() != size {
+                return Err(ParseError::ColumnCount {
+                    line: row_index + 1,
+                    expected: size,
+                    found: row.len(),
+                });
+            }
+            if let Some((column_index, value)) = row
+                .iter()
+                .enumerate()
+                .find(|(_, v)| usize::from(**v) > size)
+            {
+                return Err(ParseError::InvalidValue {
+                    row: row_index + 1,
+                    column: column_index + 1,
+                    value: *value,
+                });
+            }
+        }
+        let mut grid = Grid::new(fields, box_width, box_height);
+        if let Some(alphabet) = alphabet {
+            grid.set_alphabet(alphabet)?;
+        }
+        Ok(grid)
+    }
+}
+
 impl Grid {
-    pub fn new(fields: Vec<Vec<u8>>) -> Grid {
+    /// Creates a new grid from its rows. Each parcel spans `box_width`
+    /// columns and `box_height` rows, so there must be
+    /// `box_width * box_height` rows and columns.
+    /// The grid uses the default alphabet for its size, see
+    /// `Alphabet::default_for`.
+    pub fn new(fields: Vec<Vec<u8>>, box_width: u8, box_height: u8) -> Grid {
+        let alphabet = Alphabet::default_for(fields.len());
+        let used_values = UsedValues::new(fields.len());
         let mut grid = Grid {
             fields,
+            box_width,
+            box_height,
+            alphabet,
+            used_values,
             mutable_fields: vec![],
         };
 
+        for r in 0..grid.size() {
+            for c in 0..grid.size() {
+                let field = Field::new(r, c);
+                let value = grid.get(&field);
+                if value != 0 {
+                    for unit in grid.get_units(&field).iter() {
+                        grid.used_values.add(unit, value);
+                    }
+                }
+            }
+        }
+
         // Calculate mutable fields once and cache fields.
         let mutable_fields = grid.get_mutable_fields();
         grid.mutable_fields = mutable_fields;
@@ -30,24 +213,100 @@ impl Grid {
         grid
     }
 
+    /// Returns the most square parcel dimensions `(box_width, box_height)` for
+    /// a sudoku of the given size, e.g. 3x3 for 9x9 and 3x2 for 6x6 sudokus.
+    /// Parcels are never higher than wide. Sizes that are prime or exceed
+    /// `common::MAX_SIZE` are not supported.
+    pub fn default_box_size(size: usize) -> Option<(u8, u8)> {
+        if size > common::MAX_SIZE {
+            return None;
+        }
+        (2..=size)
+            .take_while(|h| h * h <= size)
+            .filter(|h| size.is_multiple_of(*h))
+            .last()
+            .map(|h| ((size / h) as u8, h as u8))
+    }
+
+    /// Makes sure that parcels of the given dimensions tile a sudoku of the
+    /// given size.
+    pub fn check_box_size(size: usize, box_width: u8, box_height: u8) -> Result<(), ParseError> {
+        if size > common::MAX_SIZE {
+            return Err(ParseError::UnsupportedSize { size });
+        }
+        if box_width < 1
+            || box_height < 1
+            || usize::from(box_width) * usize::from(box_height) != size
+        {
+            return Err(ParseError::BoxSize {
+                box_width: usize::from(box_width),
+                box_height: usize::from(box_height),
+                size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the number of rows and columns, which is also the number of
+    /// parcels and the largest value of a field.
+    pub fn size(&self) -> u8 {
+        self.fields.len() as u8
+    }
+
+    /// Returns the number of columns of a parcel.
+    pub fn box_width(&self) -> u8 {
+        self.box_width
+    }
+
+    /// Returns the number of rows of a parcel.
+    pub fn box_height(&self) -> u8 {
+        self.box_height
+    }
+
+    /// Returns the symbols that are used to read and print the grid.
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// Replaces the symbols that are used to read and print the grid.
+    /// The alphabet must have one symbol per value.
+    pub fn set_alphabet(&mut self, alphabet: Alphabet) -> Result<(), ParseError> {
+        if alphabet.len() != self.fields.len() {
+            return Err(ParseError::AlphabetSize {
+                expected: self.fields.len(),
+                found: alphabet.len(),
+            });
+        }
+        self.alphabet = alphabet;
+        Ok(())
+    }
+
     /// Returns all field indices (row, column) in a parcel.
-    pub fn get_parcel_fields(parcel_index: u8) -> Vec<Field> {
-        let col_start = (parcel_index % 3) * 3;
-        let row_start = (parcel_index / 3) * 3;
+    pub fn get_parcel_fields(&self, parcel_index: u8) -> Vec<Field> {
+        let (row_start, col_start) = self.get_parcel_origin(parcel_index);
         let mut fields: Vec<Field> = vec![];
-        for r in 0..3 {
-            for c in 0..3 {
+        for r in 0..self.box_height {
+            for c in 0..self.box_width {
                 fields.push(Field::new(row_start + r, col_start + c));
             }
         }
         fields
     }
 
+    /// Returns the field index (row, column) of the upper left field of a
+    /// parcel.
+    fn get_parcel_origin(&self, parcel_index: u8) -> (u8, u8) {
+        let parcels_per_row = self.size() / self.box_width;
+        let row_start = (parcel_index / parcels_per_row) * self.box_height;
+        let col_start = (parcel_index % parcels_per_row) * self.box_width;
+        (row_start, col_start)
+    }
+
     fn get_mutable_fields(&self) -> Vec<Field> {
         let mut mutable_fields: Vec<Field> = vec![];
-        for r in 0..9 {
-            for c in 0..9 {
-                if self.fields[r as usize][c as usize] == 0 {
+        for (r, row) in self.fields.iter().enumerate() {
+            for (c, value) in row.iter().enumerate() {
+                if *value == 0 {
                     mutable_fields.push(Field::new(r as u8, c as u8));
                 }
             }
@@ -55,10 +314,11 @@ impl Grid {
         mutable_fields
     }
 
-    pub fn get_parcel_index(field: &Field) -> u8 {
-        let x = field.row / 3;
-        let y = field.column / 3;
-        x * 3 + y
+    pub fn get_parcel_index(&self, field: &Field) -> u8 {
+        let parcels_per_row = self.size() / self.box_width;
+        let x = field.row / self.box_height;
+        let y = field.column / self.box_width;
+        x * parcels_per_row + y
     }
 
     pub fn get(&self, field: &Field) -> u8 {
@@ -70,39 +330,149 @@ impl Grid {
             .unwrap()
     }
 
+    /// Sets the value of a field and updates the used values of its row,
+    /// column and parcel.
     pub fn set(&mut self, field: &Field, value: u8) {
+        let old_value = self.get(field);
+        if old_value == value {
+            return;
+        }
+        for unit in self.get_units(field).iter() {
+            if old_value != 0 {
+                self.used_values.remove(unit, old_value);
+            }
+            if value != 0 {
+                self.used_values.add(unit, value);
+            }
+        }
         self.fields[field.row as usize][field.column as usize] = value;
     }
 
+    /// Returns the row, column and parcel of a field.
+    pub fn get_units(&self, field: &Field) -> [Unit; 3] {
+        [
+            Unit::Row(field.row),
+            Unit::Column(field.column),
+            Unit::Parcel(self.get_parcel_index(field)),
+        ]
+    }
+
+    /// Returns all fields of a unit.
+    pub fn get_unit_fields(&self, unit: &Unit) -> Vec<Field> {
+        match *unit {
+            Unit::Row(row) => (0..self.size()).map(|c| Field::new(row, c)).collect(),
+            Unit::Column(column) => (0..self.size()).map(|r| Field::new(r, column)).collect(),
+            Unit::Parcel(index) => self.get_parcel_fields(index),
+        }
+    }
+
+    /// Returns all other fields that share a row, column or parcel with a
+    /// field.
+    pub fn get_peers(&self, field: &Field) -> Vec<Field> {
+        let mut peers: Vec<Field> = vec![];
+        for unit in self.get_units(field).iter() {
+            for peer in self.get_unit_fields(unit) {
+                if peer != *field && !peers.contains(&peer) {
+                    peers.push(peer);
+                }
+            }
+        }
+        peers
+    }
+
+    /// Returns the values that are used at least once in a unit.
+    pub fn get_used_values(&self, unit: &Unit) -> ValueSet {
+        self.used_values.sets[self.used_values.index(unit)]
+    }
+
+    /// Returns how often a value occurs in a unit.
+    pub fn count(&self, unit: &Unit, value: u8) -> u8 {
+        self.used_values.count(unit, value)
+    }
+
+    /// Returns true if no value occurs more than once in a unit.
+    pub fn is_unique(&self, unit: &Unit) -> bool {
+        let used_values = self.get_used_values(unit);
+        used_values.iter().all(|v| self.count(unit, v) == 1)
+    }
+
+    /// Returns the values that are not used in the row, column and parcel of
+    /// a field. The value of the field itself is not a candidate.
+    pub fn get_candidates(&self, field: &Field) -> ValueSet {
+        let used_values = self
+            .get_units(field)
+            .iter()
+            .fold(ValueSet::default(), |used, unit| {
+                used.union(self.get_used_values(unit))
+            });
+        ValueSet::full(self.size()).difference(used_values)
+    }
+
     pub fn fmt(&self) -> String {
+        self.fmt_boxed(&[])
+    }
+
+    /// Formats the grid like `fmt`, but highlights the given fields with
+    /// ANSI escape codes, e.g. to point out conflicts in a terminal.
+    pub fn fmt_highlighted(&self, fields: &[Field]) -> String {
+        self.fmt_boxed(fields)
+    }
+
+    fn fmt_boxed(&self, highlighted: &[Field]) -> String {
+        let size = self.fields.len();
+        let box_width = usize::from(self.box_width);
+        let box_height = usize::from(self.box_height);
+        let blank = self.alphabet.blank('x');
         let mut out = String::new();
         for (i, row) in self.fields.iter().enumerate() {
-            if i > 0 && i % 3 == 0 {
-                out += &iter::repeat("-").take(11).collect::<String>()[..];
+            if i > 0 && i % box_height == 0 {
+                out += &"-".repeat(size + size / box_width - 1);
                 out += "\n";
             }
             for (j, v) in row.iter().enumerate() {
-                if j > 0 && j % 3 == 0 {
+                if j > 0 && j % box_width == 0 {
                     out += "|";
                 }
-                if v == &0 {
-                    out += "x";
+                let symbol = self.alphabet.symbol_of(*v, blank);
+                if highlighted.contains(&Field::new(i as u8, j as u8)) {
+                    out += &format!("\x1b[1;31m{}\x1b[0m", symbol);
                 } else {
-                    let val = format!("{}", v);
-                    out += &val[..];
+                    out.push(symbol);
                 }
             }
-            if i < self.fields.len() - 1 {
+            if i < size - 1 {
                 out += "\n";
             }
         }
         out
     }
 
+    /// Formats the grid in the compact line format, e.g.
+    /// `4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......`.
+    pub fn fmt_line(&self) -> String {
+        let blank = self.alphabet.blank('.');
+        self.fields
+            .iter()
+            .flatten()
+            .map(|v| self.alphabet.symbol_of(*v, blank))
+            .collect()
+    }
+
+    /// Formats the grid in the given format.
+    pub fn fmt_as(&self, format: Format) -> String {
+        match format {
+            Format::Boxed => self.fmt(),
+            Format::Line => self.fmt_line(),
+            Format::Json => serde_json::to_string(self).unwrap(),
+        }
+    }
+
+    #[allow(dead_code)]
     pub fn get_row(&self, row_index: u8) -> Vec<u8> {
         self.fields.get(row_index as usize).unwrap().clone()
     }
 
+    #[allow(dead_code)]
     pub fn get_col(&self, col_index: u8) -> Vec<u8> {
         self.fields
             .clone()
@@ -112,11 +482,10 @@ impl Grid {
     }
 
     pub fn get_parcel(&self, index: u8) -> Vec<Vec<u8>> {
-        let start_row = (index / 3) * 3;
-        let start_col = (index % 3) * 3;
-        let mut parcel = vec![vec![0; 3]; 3];
-        for ci in 0..3 {
-            for ri in 0..3 {
+        let (start_row, start_col) = self.get_parcel_origin(index);
+        let mut parcel = vec![vec![0; self.box_width as usize]; self.box_height as usize];
+        for ci in 0..self.box_width {
+            for ri in 0..self.box_height {
                 let row = start_row + ri;
                 let col = start_col + ci;
                 parcel[ri as usize][ci as usize] = self.get(&Field::new(row, col))
@@ -136,10 +505,10 @@ impl Grid {
 
     /// Returns all field indicies (row, column) of a mutable fields in a parcel.
     pub fn get_mutable_fields_of_parcel(&self, parcel_index: u8) -> Vec<Field> {
-        let parcel_fields = Grid::get_parcel_fields(parcel_index);
+        let parcel_fields = self.get_parcel_fields(parcel_index);
         parcel_fields
             .into_iter()
-            .filter(|f| self.mutable_fields.contains(&f))
+            .filter(|f| self.mutable_fields.contains(f))
             .collect()
     }
 }
@@ -150,15 +519,16 @@ mod tests {
 
     #[test]
     fn it_should_return_field_value() {
-        let grid = Grid::new(vec![vec![0; 9]; 9]);
+        let grid = Grid::new(vec![vec![0; 9]; 9], 3, 3);
         assert_eq!(grid.get(&Field::new(0, 0)), 0);
         assert_eq!(grid.get(&Field::new(8, 8)), 0);
     }
 
     #[test]
     fn it_should_list_all_parcel_fields() {
+        let grid = Grid::new(vec![vec![0; 9]; 9], 3, 3);
         assert_eq!(
-            Grid::get_parcel_fields(0),
+            grid.get_parcel_fields(0),
             vec![
                 Field::new(0, 0),
                 Field::new(0, 1),
@@ -172,7 +542,7 @@ mod tests {
             ]
         );
         assert_eq!(
-            Grid::get_parcel_fields(7),
+            grid.get_parcel_fields(7),
             vec![
                 Field::new(6, 3),
                 Field::new(6, 4),
@@ -186,4 +556,112 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn it_should_list_rectangular_parcel_fields() {
+        let grid = Grid::new(vec![vec![0; 6]; 6], 3, 2);
+        assert_eq!(
+            grid.get_parcel_fields(3),
+            vec![
+                Field::new(2, 3),
+                Field::new(2, 4),
+                Field::new(2, 5),
+                Field::new(3, 3),
+                Field::new(3, 4),
+                Field::new(3, 5)
+            ]
+        );
+        assert_eq!(grid.get_parcel_index(&Field::new(5, 2)), 4);
+        assert_eq!(grid.get_parcel_index(&Field::new(3, 4)), 3);
+    }
+
+    #[test]
+    fn it_should_track_used_values() {
+        let mut grid = Grid::new(vec![vec![0; 4]; 4], 2, 2);
+        grid.set(&Field::new(0, 0), 1);
+        grid.set(&Field::new(1, 1), 2);
+        assert_eq!(
+            grid.get_candidates(&Field::new(0, 1))
+                .iter()
+                .collect::<Vec<u8>>(),
+            vec![3, 4]
+        );
+        assert_eq!(
+            grid.get_candidates(&Field::new(3, 0))
+                .iter()
+                .collect::<Vec<u8>>(),
+            vec![2, 3, 4]
+        );
+
+        // Duplicates are counted, so removing one of them keeps the value.
+        grid.set(&Field::new(0, 1), 1);
+        assert!(!grid.is_unique(&Unit::Row(0)));
+        assert_eq!(grid.count(&Unit::Parcel(0), 1), 2);
+        grid.set(&Field::new(0, 1), 0);
+        assert!(grid.is_unique(&Unit::Row(0)));
+        assert_eq!(grid.count(&Unit::Row(0), 1), 1);
+        grid.set(&Field::new(0, 0), 3);
+        assert_eq!(
+            grid.get_used_values(&Unit::Column(0))
+                .iter()
+                .collect::<Vec<u8>>(),
+            vec![3]
+        );
+    }
+
+    #[test]
+    fn it_should_highlight_fields() {
+        let mut grid = Grid::new(vec![vec![0; 4]; 4], 2, 2);
+        grid.set(&Field::new(0, 0), 1);
+        assert_eq!(
+            grid.fmt_highlighted(&[Field::new(0, 0)]).lines().next(),
+            Some("\x1b[1;31m1\x1b[0mx|xx")
+        );
+    }
+
+    #[test]
+    fn it_should_pick_default_box_sizes() {
+        assert_eq!(Grid::default_box_size(4), Some((2, 2)));
+        assert_eq!(Grid::default_box_size(6), Some((3, 2)));
+        assert_eq!(Grid::default_box_size(9), Some((3, 3)));
+        assert_eq!(Grid::default_box_size(12), Some((4, 3)));
+        assert_eq!(Grid::default_box_size(16), Some((4, 4)));
+        assert_eq!(Grid::default_box_size(25), Some((5, 5)));
+        assert_eq!(Grid::default_box_size(7), None);
+    }
+
+    #[test]
+    fn it_should_format_rectangular_parcels() {
+        let mut grid = Grid::new(vec![vec![0; 6]; 6], 3, 2);
+        grid.set(&Field::new(0, 0), 1);
+        grid.set(&Field::new(5, 5), 6);
+        assert_eq!(
+            grid.fmt(),
+            "1xx|xxx\nxxx|xxx\n-------\nxxx|xxx\nxxx|xxx\n-------\nxxx|xxx\nxxx|xx6"
+        );
+    }
+
+    #[test]
+    fn it_should_serialize_box_size() {
+        let grid = Grid::new(vec![vec![0; 6]; 6], 3, 2);
+        let json = serde_json::to_string(&grid).unwrap();
+        assert!(json.starts_with("{\"box_width\":3,\"box_height\":2,"));
+        let parsed: Grid = serde_json::from_str(&json).unwrap();
+        assert_eq!((parsed.box_width(), parsed.box_height()), (3, 2));
+
+        let parsed: Grid =
+            serde_json::from_str(&serde_json::to_string(&grid.fields).unwrap()).unwrap();
+        assert_eq!((parsed.box_width(), parsed.box_height()), (3, 2));
+    }
+
+    #[test]
+    fn it_should_format_with_alphabet() {
+        let mut grid = Grid::new(vec![vec![0; 4]; 4], 2, 2);
+        grid.set(&Field::new(0, 0), 1);
+        grid.set(&Field::new(0, 1), 4);
+        grid.set_alphabet(Alphabet::new("WXYZ").unwrap()).unwrap();
+        assert_eq!(grid.fmt(), "WZ|..\n..|..\n-----\n..|..\n..|..");
+        assert_eq!(grid.fmt_line(), "WZ..............");
+        assert!(grid.set_alphabet(Alphabet::default_for(9)).is_err());
+    }
 }
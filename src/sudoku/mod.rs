@@ -1,14 +1,36 @@
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
 
 mod field;
 pub mod solver;
 pub use field::Field;
+mod alphabet;
+pub use alphabet::Alphabet;
+mod candidate_grid;
+pub use candidate_grid::CandidateGrid;
+mod cnf;
+pub use cnf::Cnf;
 mod common;
+mod conflict;
+pub use conflict::Conflict;
+mod error;
+pub use error::ParseError;
 mod grid;
-pub use grid::Grid;
-
-#[derive(Debug)]
+pub use grid::{Format, Grid};
+mod parser;
+mod report;
+pub use report::Report;
+mod solutions;
+pub use solutions::Uniqueness;
+mod unit;
+pub use unit::Unit;
+mod value_set;
+pub use value_set::ValueSet;
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Sudoku {
     pub grid: Grid,
 }
@@ -20,79 +42,135 @@ impl fmt::Display for Sudoku {
 }
 
 // Naming:
-// valid:  means that no duplicated values are in a row or parcel (with the
-//         exception of the value 0)
+// valid:  means that no duplicated values are in a row, column or parcel
+//         (with the exception of the value 0)
 // done:   means that all (missing) values have been filled
 // field:  a 1x1 field in the grid
-// parcel: a 3x3 field group (numbered 0 - 8, row major)
+// parcel: a box_height x box_width field group (numbered row major, e.g.
+//         0 - 8 with 3x3 fields each in a 9x9 sudoku)
 
 impl Sudoku {
     /// Creates a new sudoku instance.
     /// Make sure to run the `read` method afterwards to read a sudoku from a
     /// file.
     pub fn new() -> Sudoku {
-        let grid = Grid::new(vec![vec![0; 9]; 9]);
+        let grid = Grid::new(vec![vec![0; 9]; 9], 3, 3);
         Sudoku { grid }
     }
 
-    /// Reads a sudoku from a file.
-    pub fn read(&mut self, file: &str) -> () {
-        let content = std::fs::read_to_string(file).unwrap();
-        let res: Vec<Vec<_>> = content
-            .split("\n")
-            .filter(|l| !l.contains("#")) // remove comments
-            .filter(|l| !l.contains("-")) // remove parcel group separators
-            .map(|l| {
-                l.replace("|", "") // remove grid lines
-                    .replace("x", "0")
-                    .chars()
-                    .map(|c| c.to_string().parse::<u8>().unwrap())
-                    .collect()
-            })
-            .collect();
-        self.grid = Grid::new(res);
+    /// Creates a new empty sudoku that uses the given alphabet. Its size is
+    /// the number of symbols of the alphabet.
+    /// Sudokus that are read afterwards with `read` or `read_from` use this
+    /// alphabet if it fits their size.
+    pub fn with_alphabet(alphabet: &Alphabet) -> Result<Sudoku, ParseError> {
+        let size = alphabet.len();
+        let (box_width, box_height) =
+            Grid::default_box_size(size).ok_or(ParseError::UnsupportedSize { size })?;
+        let mut grid = Grid::new(vec![vec![0; size]; size], box_width, box_height);
+        grid.set_alphabet(alphabet.clone())?;
+        Ok(Sudoku { grid })
+    }
+
+    /// Reads a sudoku from a file. If `file` is `-` the sudoku is read from
+    /// stdin instead.
+    /// See `Sudoku::from_str` for the expected format.
+    pub fn read(&mut self, file: &str) -> Result<(), ParseError> {
+        if file == "-" {
+            self.read_from(io::stdin())
+        } else {
+            self.read_from(File::open(file)?)
+        }
+    }
+
+    /// Reads a sudoku from any reader until its end.
+    /// See `Sudoku::from_str` for the expected format. The alphabet of the
+    /// current grid is used if it fits the size of the new sudoku.
+    pub fn read_from<R: Read>(&mut self, mut reader: R) -> Result<(), ParseError> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        self.grid = parser::parse(&content, Some(self.grid.alphabet()))?;
+        Ok(())
+    }
+
+    /// Parses a sudoku given in the JSON format, see `Report`.
+    pub fn from_json(content: &str) -> Result<Sudoku, ParseError> {
+        let grid = parser::parse_json(content)?;
+        Ok(Sudoku { grid })
+    }
+
+    /// Parses a sudoku given in the compact line format. The values are read
+    /// with `alphabet` if it fits the size of the sudoku.
+    /// The `line_number` is only used to report errors, which is useful when
+    /// reading files that contain several sudokus.
+    pub fn from_line(
+        line: &str,
+        line_number: usize,
+        alphabet: Option<&Alphabet>,
+    ) -> Result<Sudoku, ParseError> {
+        let grid = parser::parse_line(line, line_number, alphabet)?;
+        Ok(Sudoku { grid })
     }
 
     #[allow(dead_code)]
     pub fn is_valid_row(&self, row_index: u8) -> bool {
-        let row = self.grid.get_row(row_index);
-        common::has_only_unique_digits(&row)
+        self.grid.is_unique(&Unit::Row(row_index))
     }
 
     #[allow(dead_code)]
     pub fn is_valid_col(&self, col_index: u8) -> bool {
-        let column = self.grid.get_col(col_index);
-        common::has_only_unique_digits(&column)
+        self.grid.is_unique(&Unit::Column(col_index))
     }
 
     #[allow(dead_code)]
     pub fn is_valid_parcel(&self, parcel_index: u8) -> bool {
-        let parcel = self.grid.get_parcel(parcel_index);
-        common::has_only_unique_digits(&parcel.into_iter().flatten().collect::<Vec<u8>>())
+        self.grid.is_unique(&Unit::Parcel(parcel_index))
     }
 
     #[allow(dead_code)]
     fn is_valid_field(&self, field: &Field) -> bool {
-        let parcel_index = Grid::get_parcel_index(&field);
+        let parcel_index = self.grid.get_parcel_index(field);
         self.is_valid_row(field.row)
             && self.is_valid_col(field.column)
             && self.is_valid_parcel(parcel_index)
     }
 
-    #[allow(dead_code)]
     fn is_valid(&self) -> bool {
-        for parcel_index in 0..9 {
-            if !self.is_valid_parcel(parcel_index) {
-                return false;
+        self.get_conflicts().is_empty()
+    }
+
+    /// Returns every pair of fields that contain the same value in a row,
+    /// column or parcel. Fields that clash in several units (e.g. in a row
+    /// and a parcel) are listed once per unit.
+    pub fn get_conflicts(&self) -> Vec<Conflict> {
+        let mut conflicts = vec![];
+        for unit in Unit::all(self.grid.size()) {
+            if self.grid.is_unique(&unit) {
+                continue;
+            }
+            let fields = self.grid.get_unit_fields(&unit);
+            for (i, first) in fields.iter().enumerate() {
+                let value = self.grid.get(first);
+                if value == 0 {
+                    continue;
+                }
+                for second in fields[i + 1..].iter() {
+                    if self.grid.get(second) == value {
+                        conflicts.push(Conflict {
+                            unit,
+                            value,
+                            first: first.clone(),
+                            second: second.clone(),
+                        });
+                    }
+                }
             }
         }
-        true
+        conflicts
     }
 
-    /// Simple implementation to test if the sudoku has been solved.
-    /// This implementation only checks if any field is zero and if all parcels
-    /// are valid, which means each parcel only has values from 1 - 9.
-    /// It does not test if any row or column contain duplicate values.
+    /// Tests if the sudoku has been solved, i.e. if all fields are filled and
+    /// no row, column or parcel contains a value twice (see
+    /// `get_conflicts`).
     fn is_done(&self) -> bool {
         let any_zero = self
             .grid
@@ -106,30 +184,6 @@ impl Sudoku {
         self.is_valid()
     }
 
-    fn get_field_guesses(&self, field: &Field) -> Vec<u8> {
-        let mut set_allowed: HashSet<u8> = HashSet::new();
-        for i in 1..10 {
-            set_allowed.insert(i);
-        }
-
-        let mut seen: HashSet<u8> = HashSet::new();
-        let values_row: Vec<u8> = self.grid.get_row(field.row);
-        let values_col: Vec<u8> = self.grid.get_col(field.column);
-        let values_parcel: Vec<u8> = self
-            .grid
-            .get_parcel(Grid::get_parcel_index(field))
-            .into_iter()
-            .flatten()
-            .collect();
-        seen.extend(values_row);
-        seen.extend(values_col);
-        seen.extend(values_parcel);
-
-        let mut guesses: Vec<u8> = set_allowed.difference(&seen).map(|x| *x).collect();
-        guesses.sort();
-        guesses
-    }
-
     /// Returns a grid in its unsolved representation. Every editable field
     /// is set to 0.
     pub fn get_unsolved(&self) -> Grid {
@@ -138,21 +192,44 @@ impl Sudoku {
         grid_copy
     }
 
-    /// Prints the sudoku to stdout.
-    /// This function uses `Sudoku::fmt` for the formatting of the sudoku.
+    /// Writes the sudoku to any writer.
+    /// This function uses `Grid::fmt_as` for the formatting of the sudoku.
     /// If `show_unresolved` is set to `true` the unsolved sudoku is shown next
-    /// to the solved one.
-    pub fn print(&self, show_unsolved: bool) {
-        if !show_unsolved {
-            println!("{}", Grid::fmt(&self.grid));
+    /// to the solved one. In the JSON format the unsolved sudoku is always
+    /// included, see `Report`.
+    pub fn write_to<W: Write>(
+        &self,
+        out: &mut W,
+        show_unsolved: bool,
+        format: Format,
+    ) -> io::Result<()> {
+        if format == Format::Json {
+            serde_json::to_writer(&mut *out, &Report::new(self))?;
+            writeln!(out)?;
+        } else if !show_unsolved {
+            writeln!(out, "{}", self.grid.fmt_as(format))?;
         } else {
-            let unresolved = Grid::fmt(&self.get_unsolved());
-            let solved = Grid::fmt(&self.grid);
-            let solved_iter: Vec<&str> = solved.split("\n").collect();
-            for (i, line) in unresolved.split("\n").enumerate() {
-                println!("{} -> {}", line, solved_iter.get(i).unwrap());
+            let unresolved = self.get_unsolved().fmt_as(format);
+            let solved = self.grid.fmt_as(format);
+            let solved_iter: Vec<&str> = solved.split('\n').collect();
+            for (i, line) in unresolved.split('\n').enumerate() {
+                writeln!(out, "{} -> {}", line, solved_iter.get(i).unwrap())?;
             }
         }
+        Ok(())
+    }
+}
+
+impl FromStr for Sudoku {
+    type Err = ParseError;
+
+    /// Parses a sudoku from its textual representation.
+    /// Both the boxed format (see `parser::parse_boxed`) and the compact line
+    /// format (see `parser::parse_line`) are supported. The values are read
+    /// with the default alphabet for the size of the sudoku.
+    fn from_str(content: &str) -> Result<Sudoku, ParseError> {
+        let grid = parser::parse(content, None)?;
+        Ok(Sudoku { grid })
     }
 }
 
@@ -171,13 +248,13 @@ mod tests {
     #[test]
     fn it_should_read_file() {
         let mut s = Sudoku::new();
-        s.read("examples/sudoku1-solution.txt");
+        s.read("examples/sudoku1-solution.txt").unwrap();
     }
 
     #[test]
     fn it_should_get_row_col_values() {
         let mut s = Sudoku::new();
-        s.read("examples/sudoku1-solution.txt");
+        s.read("examples/sudoku1-solution.txt").unwrap();
         assert_eq!(s.grid.get(&Field::new(0, 6)), 7);
         assert_eq!(s.grid.get(&Field::new(1, 6)), 4);
     }
@@ -185,7 +262,7 @@ mod tests {
     #[test]
     fn it_should_get_parcels() {
         let mut s = Sudoku::new();
-        s.read("examples/sudoku1-solution.txt");
+        s.read("examples/sudoku1-solution.txt").unwrap();
         assert_eq!(
             s.grid.get_parcel(0),
             vec![vec![4, 3, 5], vec![6, 8, 2], vec![1, 9, 7]]
@@ -199,7 +276,7 @@ mod tests {
     #[test]
     fn it_should_test_parcel_validity() {
         let mut s = Sudoku::new();
-        s.read("examples/sudoku1-solution.txt");
+        s.read("examples/sudoku1-solution.txt").unwrap();
         assert!(s.is_valid_parcel(0));
 
         s.grid.set(&Field::new(0, 0), 1);
@@ -210,45 +287,68 @@ mod tests {
     #[test]
     fn it_should_give_rows() {
         let mut s = Sudoku::new();
-        s.read("examples/sudoku1-solution.txt");
+        s.read("examples/sudoku1-solution.txt").unwrap();
         assert_eq!(s.grid.get_row(2), vec![1, 9, 7, 8, 3, 4, 5, 6, 2]);
     }
 
     #[test]
     fn it_should_give_columns() {
         let mut s = Sudoku::new();
-        s.read("examples/sudoku1-solution.txt");
+        s.read("examples/sudoku1-solution.txt").unwrap();
         assert_eq!(s.grid.get_col(2), vec![5, 2, 7, 6, 4, 1, 9, 8, 3]);
     }
 
     #[test]
     fn it_should_be_valid() {
         let mut s = Sudoku::new();
-        s.read("examples/sudoku1-solution.txt");
+        s.read("examples/sudoku1-solution.txt").unwrap();
         assert!(s.is_valid());
 
         s.grid.set(&Field::new(0, 0), 6);
         assert!(!s.is_valid());
     }
 
+    #[test]
+    fn it_should_list_conflicts() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku1-solution.txt").unwrap();
+        assert_eq!(s.get_conflicts(), vec![]);
+
+        s.grid.set(&Field::new(0, 0), 6);
+        let conflicts = s.get_conflicts();
+        assert_eq!(
+            conflicts
+                .iter()
+                .map(|c| (c.unit, c.first.clone(), c.second.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Unit::Row(0), Field::new(0, 0), Field::new(0, 4)),
+                (Unit::Column(0), Field::new(0, 0), Field::new(1, 0)),
+                (Unit::Parcel(0), Field::new(0, 0), Field::new(1, 0)),
+            ]
+        );
+        assert!(conflicts.iter().all(|c| c.value == 6));
+        assert!(!s.is_done());
+    }
+
     #[test]
     fn it_should_flag_solution_as_done() {
         let mut s = Sudoku::new();
-        s.read("examples/sudoku1-solution.txt");
+        s.read("examples/sudoku1-solution.txt").unwrap();
         assert!(s.is_done());
     }
 
     #[test]
     fn it_should_flag_unsolved_sudoko_as_not_done() {
         let mut s = Sudoku::new();
-        s.read("examples/sudoku1.txt");
+        s.read("examples/sudoku1.txt").unwrap();
         assert!(!s.is_done());
     }
 
     #[test]
     fn it_should_mark_mutable_fields() {
         let mut s = Sudoku::new();
-        s.read("examples/sudoku1.txt");
+        s.read("examples/sudoku1.txt").unwrap();
 
         assert_eq!(
             s.grid.mutable_fields,
@@ -305,14 +405,15 @@ mod tests {
     #[test]
     fn it_should_have_correct_field_guesses() {
         let mut s = Sudoku::new();
-        s.read("examples/sudoku1.txt");
-        assert_eq!(s.get_field_guesses(&Field::new(0, 0)), vec![3, 4, 5]);
-        assert_eq!(s.get_field_guesses(&Field::new(8, 8)), vec![2, 5, 9]);
+        s.read("examples/sudoku1.txt").unwrap();
+        let guesses = |field| s.grid.get_candidates(&field).iter().collect::<Vec<u8>>();
+        assert_eq!(guesses(Field::new(0, 0)), vec![3, 4, 5]);
+        assert_eq!(guesses(Field::new(8, 8)), vec![2, 5, 9]);
     }
     #[test]
     fn it_should_reset_values() {
         let mut s = Sudoku::new();
-        s.read("examples/sudoku1.txt");
+        s.read("examples/sudoku1.txt").unwrap();
 
         // Sanity check; (0,0) must be mutable field
         assert_eq!(s.grid.mutable_fields[0], Field::new(0, 0));
@@ -324,10 +425,98 @@ mod tests {
         assert_eq!(s.grid.get(&Field::new(0, 0)), 0);
     }
 
+    #[test]
+    fn it_should_read_from_reader() {
+        let content = std::fs::read_to_string("examples/sudoku1.txt").unwrap();
+        let mut s = Sudoku::new();
+        s.read_from(content.as_bytes()).unwrap();
+        assert_eq!(s.grid.get(&Field::new(0, 3)), 2);
+    }
+
+    #[test]
+    fn it_should_write_to_writer() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku1-solution.txt").unwrap();
+        let mut out: Vec<u8> = vec![];
+        s.write_to(&mut out, false, Format::Line).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "435269781682571493197834562826195347374682915951743628519326874248957136763418259\n"
+        );
+    }
+
+    #[test]
+    fn it_should_read_with_alphabet() {
+        let content = std::fs::read_to_string("examples/sudoku1.txt").unwrap();
+        let wordoku = content
+            .chars()
+            .map(|c| match c.to_digit(10) {
+                Some(d) if d > 0 => "WORDSPLAY".chars().nth(d as usize - 1).unwrap(),
+                _ => c,
+            })
+            .collect::<String>();
+
+        let alphabet = Alphabet::new("WORDSPLAY").unwrap();
+        let mut s = Sudoku::with_alphabet(&alphabet).unwrap();
+        s.read_from(wordoku.as_bytes()).unwrap();
+        assert_eq!(s.grid.get(&Field::new(0, 3)), 2);
+        assert_eq!(s.grid.alphabet(), &alphabet);
+    }
+
+    #[test]
+    fn it_should_fail_on_missing_file() {
+        let mut s = Sudoku::new();
+        match s.read("examples/does-not-exist.txt") {
+            Err(ParseError::Io(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_should_report_invalid_characters() {
+        let content = "# comment\nxxx|26x|7x1\n68x|x7a|x9x\n";
+        match content.parse::<Sudoku>() {
+            Err(ParseError::InvalidCharacter {
+                line,
+                column,
+                character,
+            }) => {
+                assert_eq!((line, column, character), (3, 7, 'a'));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_should_report_short_rows() {
+        let content = "xxx|26x|7x1\n68x|x7x|x9\n";
+        match content.parse::<Sudoku>() {
+            Err(ParseError::ColumnCount {
+                line,
+                expected,
+                found,
+            }) => {
+                assert_eq!((line, expected, found), (2, 9, 8));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_should_report_missing_rows() {
+        let content = "xxx|26x|7x1\n68x|x7x|x9x\n";
+        match content.parse::<Sudoku>() {
+            Err(ParseError::RowCount { expected, found }) => {
+                assert_eq!((expected, found), (9, 2));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
     #[test]
     fn it_should_list_all_mutable_parcel_fields() {
         let mut s = Sudoku::new();
-        s.read("examples/sudoku1.txt");
+        s.read("examples/sudoku1.txt").unwrap();
         let mutable_fields = s.grid.get_mutable_fields_of_parcel(5);
 
         assert_eq!(
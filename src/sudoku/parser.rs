@@ -0,0 +1,499 @@
+use super::alphabet::Alphabet;
+use super::candidate_grid::CandidateGrid;
+use super::common;
+use super::error::ParseError;
+use super::grid::Grid;
+use super::report::Report;
+use super::value_set::ValueSet;
+
+/// Parses a sudoku and detects its format automatically.
+/// If the input starts with `{` it is parsed with `parse_json`. If it
+/// consists of a single line without grid lines it is parsed with
+/// `parse_line`. Pencil marks, whose fields are separated by spaces, are
+/// parsed with `parse_candidates` and only their placed values are kept.
+/// Everything else is parsed with `parse_boxed`.
+/// See `parse_boxed` for the meaning of `alphabet`.
+pub fn parse(content: &str, alphabet: Option<&Alphabet>) -> Result<Grid, ParseError> {
+    if content.trim_start().starts_with('{') {
+        return parse_json(content);
+    }
+
+    let lines: Vec<(usize, &str)> = content
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim_end()))
+        .filter(|(_, l)| !l.is_empty() && !l.contains('#'))
+        .collect();
+
+    match lines.as_slice() {
+        [(line_number, line)] if !line.contains('|') => parse_line(line, *line_number, alphabet),
+        [(_, line), ..] if line.contains(' ') => {
+            parse_candidates(content, alphabet).map(|candidates| candidates.grid().clone())
+        }
+        _ => parse_boxed(content, alphabet),
+    }
+}
+
+/// Parses a sudoku in the boxed format, e.g.
+/// ```text
+/// xxx|26x|7x1
+/// 68x|x7x|x9x
+/// 19x|xx4|5xx
+/// -----------
+/// ...
+/// ```
+/// Lines containing `#` are treated as comments and lines containing `-` as
+/// parcel group separators. Empty fields are marked with `x`, `.` or `0`.
+///
+/// The size of the sudoku is given by the number of fields in the first row.
+/// The parcel dimensions are derived from the position of the first grid
+/// line (`|`) and the first separator. If they are missing, the default
+/// dimensions for the size are used, see `Grid::default_box_size`.
+///
+/// The values are read with `alphabet` if it has as many symbols as the
+/// sudoku has rows, otherwise the default alphabet for the size is used, see
+/// `Alphabet::default_for`.
+pub fn parse_boxed(content: &str, alphabet: Option<&Alphabet>) -> Result<Grid, ParseError> {
+    let mut symbols: Option<Alphabet> = None;
+    let mut rows: Vec<Vec<u8>> = vec![];
+    let mut size: Option<usize> = None;
+    let mut box_width: Option<usize> = None;
+    let mut box_height: Option<usize> = None;
+    for (line_index, line) in content.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() || line.contains('#') {
+            continue;
+        }
+        if line.contains('-') {
+            // The first separator tells the number of rows of a parcel.
+            if box_height.is_none() {
+                box_height = Some(rows.len());
+            }
+            continue;
+        }
+
+        let size = *size.get_or_insert_with(|| line.chars().filter(|c| *c != '|').count());
+        let symbols = symbols.get_or_insert_with(|| choose_alphabet(size, alphabet));
+        let mut row: Vec<u8> = vec![];
+        for (column_index, character) in line.chars().enumerate() {
+            if character == '|' {
+                // The first grid line tells the number of columns of a parcel.
+                if box_width.is_none() {
+                    box_width = Some(row.len());
+                }
+                continue;
+            }
+            match symbols.value_of(character) {
+                Some(value) => row.push(value),
+                None => {
+                    return Err(ParseError::InvalidCharacter {
+                        line: line_index + 1,
+                        column: column_index + 1,
+                        character,
+                    })
+                }
+            }
+        }
+        if row.len() != size {
+            return Err(ParseError::ColumnCount {
+                line: line_index + 1,
+                expected: size,
+                found: row.len(),
+            });
+        }
+        rows.push(row);
+    }
+
+    let size = size.unwrap_or(0);
+    if size > common::MAX_SIZE {
+        return Err(ParseError::UnsupportedSize { size });
+    }
+    if rows.len() != size || size == 0 {
+        return Err(ParseError::RowCount {
+            expected: size,
+            found: rows.len(),
+        });
+    }
+
+    let (box_width, box_height) = match (box_width, box_height) {
+        (Some(w), Some(h)) => (w as u8, h as u8),
+        (Some(w), None) => (w as u8, (size / w.max(1)) as u8),
+        (None, Some(h)) => ((size / h.max(1)) as u8, h as u8),
+        (None, None) => Grid::default_box_size(size).ok_or(ParseError::UnsupportedSize { size })?,
+    };
+    Grid::check_box_size(size, box_width, box_height)?;
+
+    let mut grid = Grid::new(rows, box_width, box_height);
+    grid.set_alphabet(symbols.unwrap())?;
+    Ok(grid)
+}
+
+/// Parses a sudoku in the compact line format, where all fields are listed
+/// row by row in a single line, e.g.
+/// `4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......`.
+/// Empty fields are marked with `.`, `0` or `x`.
+/// The size of the sudoku is derived from the length of the line and the
+/// default parcel dimensions are used, see `Grid::default_box_size`.
+/// See `parse_boxed` for the meaning of `alphabet`.
+/// The `line_number` is only used to report errors.
+pub fn parse_line(
+    line: &str,
+    line_number: usize,
+    alphabet: Option<&Alphabet>,
+) -> Result<Grid, ParseError> {
+    let line = line.trim_end();
+    let field_count = line.chars().count();
+    let size = (1..)
+        .take_while(|n| n * n <= field_count)
+        .last()
+        .unwrap_or(0);
+    if size * size != field_count || size == 0 {
+        return Err(ParseError::FieldCount {
+            line: line_number,
+            found: field_count,
+        });
+    }
+    let (box_width, box_height) =
+        Grid::default_box_size(size).ok_or(ParseError::UnsupportedSize { size })?;
+    let symbols = choose_alphabet(size, alphabet);
+
+    let mut values: Vec<u8> = vec![];
+    for (column_index, character) in line.chars().enumerate() {
+        match symbols.value_of(character) {
+            Some(value) => values.push(value),
+            None => {
+                return Err(ParseError::InvalidCharacter {
+                    line: line_number,
+                    column: column_index + 1,
+                    character,
+                })
+            }
+        }
+    }
+
+    let rows = values.chunks(size).map(|row| row.to_vec()).collect();
+    let mut grid = Grid::new(rows, box_width, box_height);
+    grid.set_alphabet(symbols)?;
+    Ok(grid)
+}
+
+/// Parses pencil marks in the format of `CandidateGrid::fmt`.
+/// Each line holds one row of marks of every field. The marks of a field are
+/// separated by spaces or `|`, the fields of different rows by empty lines
+/// or `-` separators. The dimensions of the parcels are given by the number
+/// of marks of a field per line and the number of lines per field. Lines
+/// containing `#` are treated as comments.
+/// See `parse_boxed` for the meaning of `alphabet`.
+pub fn parse_candidates(
+    content: &str,
+    alphabet: Option<&Alphabet>,
+) -> Result<CandidateGrid, ParseError> {
+    // Group the lines with marks by the row of fields they belong to.
+    let mut field_rows: Vec<Vec<(usize, &str)>> = vec![vec![]];
+    for (line_index, line) in content.lines().enumerate() {
+        let line = line.trim_end();
+        if line.contains('#') {
+            continue;
+        }
+        if line.is_empty() || line.contains('-') {
+            if !field_rows.last().unwrap().is_empty() {
+                field_rows.push(vec![]);
+            }
+            continue;
+        }
+        field_rows.last_mut().unwrap().push((line_index + 1, line));
+    }
+    if field_rows.last().unwrap().is_empty() {
+        field_rows.pop();
+    }
+
+    let first_line = field_rows.first().and_then(|lines| lines.first());
+    let (box_width, marks_per_line) = match first_line {
+        Some((_, line)) => (
+            line.chars().take_while(|c| !is_mark_separator(*c)).count(),
+            line.chars().filter(|c| !is_mark_separator(*c)).count(),
+        ),
+        None => (0, 0),
+    };
+    let size = marks_per_line / box_width.max(1);
+    let box_height = field_rows.first().map(Vec::len).unwrap_or(0);
+    if size > common::MAX_SIZE {
+        return Err(ParseError::UnsupportedSize { size });
+    }
+    if field_rows.len() != size || size == 0 {
+        return Err(ParseError::RowCount {
+            expected: size,
+            found: field_rows.len(),
+        });
+    }
+    Grid::check_box_size(size, box_width as u8, box_height as u8)?;
+
+    let symbols = choose_alphabet(size, alphabet);
+    let invalid_character =
+        |line: usize, column: usize, character: char| ParseError::InvalidCharacter {
+            line,
+            column: column + 1,
+            character,
+        };
+    // The marks of every field in row major order, together with their
+    // position in the input for error messages.
+    let mut marks: Vec<Vec<(u8, usize, usize, char)>> = vec![vec![]; size * size];
+    for (r, lines) in field_rows.iter().enumerate() {
+        if lines.len() != box_height {
+            return Err(ParseError::RowCount {
+                expected: size * box_height,
+                found: field_rows.iter().map(Vec::len).sum(),
+            });
+        }
+        for (line_number, line) in lines.iter() {
+            let line_marks: Vec<(usize, char)> = line
+                .chars()
+                .enumerate()
+                .filter(|(_, c)| !is_mark_separator(*c))
+                .collect();
+            if line_marks.len() != marks_per_line {
+                return Err(ParseError::ColumnCount {
+                    line: *line_number,
+                    expected: marks_per_line,
+                    found: line_marks.len(),
+                });
+            }
+            for (i, (column_index, character)) in line_marks.into_iter().enumerate() {
+                let value = symbols
+                    .value_of(character)
+                    .ok_or_else(|| invalid_character(*line_number, column_index, character))?;
+                marks[r * size + i / box_width].push((
+                    value,
+                    *line_number,
+                    column_index,
+                    character,
+                ));
+            }
+        }
+    }
+
+    let mut rows = vec![vec![0; size]; size];
+    let mut candidates = vec![];
+    for (i, field_marks) in marks.iter().enumerate() {
+        // The marks of placed values fill the whole field.
+        let placed = field_marks[0].0;
+        if placed != 0 && field_marks.iter().all(|m| m.0 == placed) {
+            rows[i / size][i % size] = placed;
+            candidates.push(Some(placed).into_iter().collect());
+            continue;
+        }
+        // Otherwise each candidate must be drawn at its own position.
+        let mut field_candidates = ValueSet::default();
+        for (position, (value, line_number, column_index, character)) in
+            field_marks.iter().enumerate()
+        {
+            if *value != 0 && usize::from(*value) != position + 1 {
+                return Err(invalid_character(*line_number, *column_index, *character));
+            }
+            field_candidates.insert(*value);
+        }
+        candidates.push(field_candidates);
+    }
+    let mut grid = Grid::new(rows, box_width as u8, box_height as u8);
+    grid.set_alphabet(symbols)?;
+    Ok(CandidateGrid::from_parts(grid, candidates))
+}
+
+fn is_mark_separator(character: char) -> bool {
+    character == ' ' || character == '|'
+}
+
+/// Returns `alphabet` if it fits a sudoku of the given size or the default
+/// alphabet for the size otherwise.
+fn choose_alphabet(size: usize, alphabet: Option<&Alphabet>) -> Alphabet {
+    match alphabet {
+        Some(alphabet) if alphabet.len() == size => alphabet.clone(),
+        _ => Alphabet::default_for(size),
+    }
+}
+
+/// Parses a sudoku in the JSON format that is also used for the output,
+/// see `Report`. Only the `puzzle` entry is used.
+pub fn parse_json(content: &str) -> Result<Grid, ParseError> {
+    let report: Report = serde_json::from_str(content)?;
+    Ok(report.puzzle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sudoku::Field;
+    use itertools::Itertools;
+
+    const LINE: &str =
+        "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+    #[test]
+    fn it_should_parse_line_format() {
+        let grid = parse_line(LINE, 1, None).unwrap();
+        assert_eq!(grid.get(&Field::new(0, 0)), 4);
+        assert_eq!(grid.get(&Field::new(0, 6)), 8);
+        assert_eq!(grid.get(&Field::new(8, 2)), 4);
+        assert_eq!(grid.mutable_fields.len(), 81 - 17);
+    }
+
+    #[test]
+    fn it_should_round_trip_line_format() {
+        let grid = parse_line(LINE, 1, None).unwrap();
+        assert_eq!(grid.fmt_line(), LINE);
+    }
+
+    #[test]
+    fn it_should_detect_line_format() {
+        let content = format!("# A comment\n{}\n", LINE);
+        let grid = parse(&content, None).unwrap();
+        assert_eq!(grid.fmt_line(), LINE);
+    }
+
+    #[test]
+    fn it_should_detect_json_format() {
+        let content = format!(
+            "{{\"puzzle\": {}}}",
+            parse_line(LINE, 1, None)
+                .unwrap()
+                .fmt_as(crate::sudoku::Format::Json)
+        );
+        let grid = parse(&content, None).unwrap();
+        assert_eq!(grid.fmt_line(), LINE);
+    }
+
+    #[test]
+    fn it_should_validate_json_grids() {
+        match parse_json("{\"puzzle\": [[1, 2, 3, 4], [1, 2, 3], [0, 0, 0, 0], [0, 0, 0, 0]]}") {
+            Err(ParseError::Json(err)) => {
+                assert!(format!("{}", err).starts_with("expected 4 fields in line 2, but found 3"))
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_should_report_wrong_line_length() {
+        match parse_line(&LINE[1..], 4, None) {
+            Err(ParseError::FieldCount { line, found }) => assert_eq!((line, found), (4, 80)),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_should_parse_small_line_format() {
+        let grid = parse_line("1.3..4.......3.4", 1, None).unwrap();
+        assert_eq!(grid.size(), 4);
+        assert_eq!((grid.box_width(), grid.box_height()), (2, 2));
+        assert_eq!(grid.get(&Field::new(3, 3)), 4);
+    }
+
+    #[test]
+    fn it_should_round_trip_pencil_marks() {
+        let grid = parse_line(LINE, 1, None).unwrap();
+        let mut candidates = CandidateGrid::from_grid(&grid);
+        candidates.eliminate(&Field::new(0, 1), 2);
+        candidates.place(&Field::new(0, 1), 6);
+
+        let content = candidates.fmt();
+        assert_eq!(content.lines().filter(|l| l.starts_with('-')).count(), 2);
+        assert_eq!(content.lines().filter(|l| l.is_empty()).count(), 6);
+        let parsed = parse_candidates(&content, None).unwrap();
+        assert_eq!(parsed.fmt(), content);
+        assert_eq!(parsed.grid().get(&Field::new(0, 1)), 6);
+        assert_eq!(parsed.grid().mutable_fields.len(), 81 - 18);
+        assert_eq!(parse(&content, None).unwrap().fmt(), parsed.grid().fmt());
+    }
+
+    #[test]
+    fn it_should_reject_misplaced_pencil_marks() {
+        let content = "11 .2|33 .2\n11 .4|33 ..\n\n.2 .2|44 12\n3. 3.|44 ..\n-----------\n\
+            .2 12|12 12\n34 34|.. .4\n\n.2 12|12 33\n.4 4.|.. 33";
+        match parse_candidates(content, None) {
+            Err(ParseError::InvalidCharacter { line, column, .. }) => {
+                assert_eq!((line, column), (11, 4))
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match parse_candidates(&content.replace("12 33", "12 3"), None) {
+            Err(ParseError::ColumnCount { line, found, .. }) => assert_eq!((line, found), (10, 7)),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_should_parse_large_boxed_format() {
+        let content = std::fs::read_to_string("examples/sudoku16.txt").unwrap();
+        let grid = parse(&content, None).unwrap();
+        assert_eq!(grid.size(), 16);
+        assert_eq!((grid.box_width(), grid.box_height()), (4, 4));
+        assert_eq!(grid.fmt(), content.lines().skip(2).join("\n"));
+    }
+
+    #[test]
+    fn it_should_parse_rectangular_parcels() {
+        let content = "1xx|xxx\nxxx|xxx\n-------\nxxx|xxx\nxxx|xxx\n-------\nxxx|xxx\nxxx|xx6";
+        let grid = parse(content, None).unwrap();
+        assert_eq!((grid.box_width(), grid.box_height()), (3, 2));
+        assert_eq!(grid.fmt(), content);
+
+        let content = "1x|xx|xx\nxx|xx|xx\nxx|xx|xx\n--------\nxx|xx|xx\nxx|xx|xx\nxx|xx|x6";
+        let grid = parse(content, None).unwrap();
+        assert_eq!((grid.box_width(), grid.box_height()), (2, 3));
+    }
+
+    #[test]
+    fn it_should_reject_invalid_parcels() {
+        let content = "1xxx|xx\nxxxx|xx\n-------\nxxxx|xx\nxxxx|xx\n-------\nxxxx|xx\nxxxx|x6";
+        match parse(content, None) {
+            Err(ParseError::BoxSize {
+                box_width,
+                box_height,
+                size,
+            }) => assert_eq!((box_width, box_height, size), (4, 2, 6)),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_should_reject_values_above_size() {
+        match parse_line("1.3..5.......3.4", 1, None) {
+            Err(ParseError::InvalidCharacter { column, .. }) => assert_eq!(column, 6),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_should_parse_with_alphabet() {
+        let wordoku = Alphabet::new("WORDSPLAY").unwrap();
+        let content = "# Wordoku\nxxx|SPx|Lx.\nxxx|xxx|xxx\nxxx|xxx|xxx\n-----------\n\
+            xxx|xxx|xxx\nxxx|xxx|xxx\nxxx|xxx|xxx\n-----------\n\
+            xxx|xxx|xxx\nxxx|xxx|xxx\nxxx|xxx|xxY";
+        let grid = parse(content, Some(&wordoku)).unwrap();
+        assert_eq!(grid.get(&Field::new(0, 3)), 5);
+        assert_eq!(grid.get(&Field::new(0, 6)), 7);
+        assert_eq!(grid.get(&Field::new(8, 8)), 9);
+        assert_eq!(grid.alphabet(), &wordoku);
+        assert_eq!(
+            grid.fmt_line().chars().take(9).collect::<String>(),
+            "...SP.L.."
+        );
+
+        // The alphabet is only used if it fits the size of the sudoku.
+        let grid = parse_line("1.3..4.......3.4", 1, Some(&wordoku)).unwrap();
+        assert_eq!(grid.alphabet(), &Alphabet::default_for(4));
+    }
+
+    #[test]
+    fn it_should_parse_hex_alphabet() {
+        let hex = Alphabet::from_name("hex").unwrap();
+        let line: String = std::iter::once('0')
+            .chain(std::iter::repeat_n('.', 254))
+            .chain(std::iter::once('F'))
+            .collect();
+        let grid = parse_line(&line, 1, Some(&hex)).unwrap();
+        assert_eq!(grid.get(&Field::new(0, 0)), 1);
+        assert_eq!(grid.get(&Field::new(15, 15)), 16);
+        assert_eq!(grid.fmt_line(), line);
+    }
+}
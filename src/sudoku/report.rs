@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::conflict::Conflict;
+use super::grid::Grid;
+use super::solutions::Uniqueness;
+use super::Sudoku;
+
+/// Machine-readable representation of a sudoku and the run that solved it.
+/// This is the shape of the JSON output and is also accepted as input, in
+/// which case only `puzzle` is required.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Report {
+    /// The given values of the sudoku, every editable field is set to 0.
+    pub puzzle: Grid,
+    /// The solved sudoku or `None` if the solver failed.
+    #[serde(default)]
+    pub solution: Option<Grid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+    /// The algorithm that found the solution, only set for portfolio runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winner: Option<String>,
+    /// The seed of the random numbers of randomized algorithms, which allows
+    /// to replay the run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tries: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<f64>,
+    /// Duplicate values of the sudoku, only set when validating a sudoku.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conflicts: Option<Vec<Conflict>>,
+    /// Number of solutions found, only set when counting solutions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solution_count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uniqueness: Option<Uniqueness>,
+}
+
+impl Report {
+    /// Creates a report of the current state of a sudoku without any
+    /// information about the solver.
+    pub fn new(sudoku: &Sudoku) -> Report {
+        Report {
+            puzzle: sudoku.get_unsolved(),
+            solution: Some(sudoku.grid.clone()),
+            algorithm: None,
+            winner: None,
+            seed: None,
+            tries: None,
+            elapsed_ms: None,
+            conflicts: None,
+            solution_count: None,
+            uniqueness: None,
+        }
+    }
+
+    /// Creates a report of a validated sudoku. The sudoku is reported as
+    /// given, without a solution.
+    pub fn from_validation(sudoku: &Sudoku, conflicts: Vec<Conflict>) -> Report {
+        let mut report = Report::new(sudoku);
+        report.puzzle = report.solution.take().unwrap();
+        report.conflicts = Some(conflicts);
+        report
+    }
+
+    /// Creates a report of the number of solutions of a sudoku, which is
+    /// reported without a solution.
+    pub fn from_count(sudoku: &Sudoku, count: usize) -> Report {
+        let mut report = Report::new(sudoku);
+        report.solution = None;
+        report.solution_count = Some(count);
+        report.uniqueness = Some(Uniqueness::from_count(count));
+        report
+    }
+
+    /// Creates a report of a solver run. If the run was not successful, the
+    /// solution is omitted.
+    pub fn from_run(
+        sudoku: &Sudoku,
+        success: bool,
+        algorithm: &str,
+        tries: u32,
+        elapsed: Duration,
+    ) -> Report {
+        let mut report = Report::new(sudoku);
+        if !success {
+            report.solution = None;
+        }
+        report.algorithm = Some(String::from(algorithm));
+        report.tries = Some(tries);
+        report.elapsed_ms = Some(elapsed.as_secs_f64() * 1000.0);
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Field;
+    use super::*;
+
+    #[test]
+    fn it_should_round_trip_json() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku1.txt").unwrap();
+        let report = Report::from_run(&s, true, "backtracing", 63, Duration::from_millis(2));
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"algorithm\":\"backtracing\""));
+        assert!(json.contains("\"tries\":63"));
+
+        let parsed: Report = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.puzzle.fmt(), s.get_unsolved().fmt());
+        assert_eq!(parsed.puzzle.mutable_fields, s.grid.mutable_fields);
+        assert_eq!(parsed.tries, Some(63));
+    }
+
+    #[test]
+    fn it_should_omit_solution_of_failed_runs() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku1.txt").unwrap();
+        let report = Report::from_run(&s, false, "montecarlo", 10, Duration::from_millis(2));
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"solution\":null"));
+    }
+
+    #[test]
+    fn it_should_report_solution_count() {
+        let s: Sudoku = "1.3..4.......3..".parse().unwrap();
+        let report = Report::from_count(&s, s.count_solutions(2));
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(
+            json.ends_with("\"solution\":null,\"solution_count\":2,\"uniqueness\":\"multiple\"}")
+        );
+    }
+
+    #[test]
+    fn it_should_report_conflicts() {
+        let s: Sudoku = "11..............".parse().unwrap();
+        let report = Report::from_validation(&s, s.get_conflicts());
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains(
+            "\"conflicts\":[{\"unit\":{\"Row\":0},\"value\":1,\
+            \"first\":{\"row\":0,\"column\":0},\"second\":{\"row\":0,\"column\":1}},"
+        ));
+        assert_eq!(report.puzzle.get(&Field::new(0, 1)), 1);
+    }
+}
@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::field::Field;
+use super::grid::Grid;
+use super::value_set::ValueSet;
+use super::Sudoku;
+
+/// Tells whether a sudoku has exactly one solution, which makes it a proper
+/// puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Uniqueness {
+    None,
+    Unique,
+    Multiple,
+}
+
+impl Uniqueness {
+    pub fn from_count(count: usize) -> Uniqueness {
+        match count {
+            0 => Uniqueness::None,
+            1 => Uniqueness::Unique,
+            _ => Uniqueness::Multiple,
+        }
+    }
+}
+
+impl fmt::Display for Uniqueness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Uniqueness::None => write!(f, "none"),
+            Uniqueness::Unique => write!(f, "unique"),
+            Uniqueness::Multiple => write!(f, "multiple"),
+        }
+    }
+}
+
+impl Sudoku {
+    /// Returns a lazy iterator over all solutions of the sudoku, see
+    /// `Solutions`. The sudoku itself is not changed.
+    pub fn solutions(&self) -> Solutions {
+        Solutions {
+            grid: self.grid.clone(),
+            stack: vec![],
+            backtrack: false,
+            done: !self.is_valid(),
+        }
+    }
+
+    /// Counts the solutions of the sudoku, but stops as soon as `limit`
+    /// solutions have been found, e.g. use a limit of 2 to check if the
+    /// solution is unique. The sudoku itself is not changed.
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        self.solutions().take(limit).count()
+    }
+}
+
+/// Iterator over the solutions of a sudoku, which are found one after
+/// another by a backtracking search that always continues with the field
+/// with the fewest candidates. Each call to `next` resumes the search where
+/// the last solution was found.
+pub struct Solutions {
+    grid: Grid,
+    /// The fields that have been filled by the search together with the
+    /// candidates that have not been tried yet.
+    stack: Vec<(Field, ValueSet)>,
+    /// Set if the last field needs another value, either because a solution
+    /// has been returned or because the search ran into a dead end.
+    backtrack: bool,
+    done: bool,
+}
+
+impl Solutions {
+    /// Tries the next candidate of the last filled field. Fields without
+    /// candidates left are cleared. Returns `false` if the search space has
+    /// been exhausted.
+    fn advance(&mut self) -> bool {
+        while let Some((field, mut candidates)) = self.stack.pop() {
+            match candidates.first() {
+                Some(value) => {
+                    self.grid.set(&field, value);
+                    candidates.remove(value);
+                    self.stack.push((field, candidates));
+                    return true;
+                }
+                None => self.grid.set(&field, 0),
+            }
+        }
+        false
+    }
+
+    fn choose_field(&self) -> Option<Field> {
+        self.grid
+            .mutable_fields
+            .iter()
+            .filter(|f| self.grid.get(f) == 0)
+            .min_by_key(|f| self.grid.get_candidates(f).len())
+            .cloned()
+    }
+}
+
+impl Iterator for Solutions {
+    type Item = Grid;
+
+    fn next(&mut self) -> Option<Grid> {
+        while !self.done {
+            if self.backtrack && !self.advance() {
+                self.done = true;
+                break;
+            }
+            self.backtrack = false;
+            let field = match self.choose_field() {
+                Some(field) => field,
+                None => {
+                    self.backtrack = true;
+                    return Some(self.grid.clone());
+                }
+            };
+            let mut candidates = self.grid.get_candidates(&field);
+            match candidates.first() {
+                Some(value) => {
+                    self.grid.set(&field, value);
+                    candidates.remove(value);
+                    self.stack.push((field, candidates));
+                }
+                None => self.backtrack = true,
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_find_unique_solution() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku1.txt").unwrap();
+        assert_eq!(s.count_solutions(10), 1);
+        assert_eq!(
+            Uniqueness::from_count(s.count_solutions(2)),
+            Uniqueness::Unique
+        );
+        assert_eq!(s.grid.fmt(), s.get_unsolved().fmt());
+    }
+
+    #[test]
+    fn it_should_stop_at_limit() {
+        let s: Sudoku = "1.3..4.......3..".parse().unwrap();
+        assert_eq!(s.count_solutions(100), 3);
+        assert_eq!(s.count_solutions(2), 2);
+        assert_eq!(
+            Uniqueness::from_count(s.count_solutions(2)),
+            Uniqueness::Multiple
+        );
+        assert_eq!(
+            format!("{}", Uniqueness::from_count(s.count_solutions(2))),
+            "multiple"
+        );
+    }
+
+    #[test]
+    fn it_should_iterate_over_solutions() {
+        let s: Sudoku = "1.3..4.......3..".parse().unwrap();
+        let solutions: Vec<String> = s.solutions().map(|g| g.fmt_line()).collect();
+        assert_eq!(
+            solutions,
+            vec!["1234341221434321", "1234341241232341", "1234342121434312"]
+        );
+        let mut solutions = s.solutions();
+        let first = solutions.next().unwrap();
+        assert_eq!(first.mutable_fields, s.grid.mutable_fields);
+        assert!(Sudoku { grid: first }.is_done());
+    }
+
+    #[test]
+    fn it_should_find_no_solution() {
+        let s: Sudoku = "1.3..4.......3.4".parse().unwrap();
+        assert_eq!(
+            Uniqueness::from_count(s.count_solutions(2)),
+            Uniqueness::None
+        );
+        let s: Sudoku = "11..............".parse().unwrap();
+        assert_eq!(s.count_solutions(2), 0);
+        assert!(s.solutions().next().is_none());
+    }
+}
@@ -0,0 +1,286 @@
+use rand::Rng;
+
+use super::super::{CandidateGrid, Field, Sudoku, ValueSet};
+use super::{CancellationToken, Observer, Outcome, Progress, Propagation, SeededRng, Solver};
+
+/// Probability that an ant chooses the value with the most pheromone instead
+/// of a random value weighted by the pheromone.
+const GREEDINESS: f32 = 0.9;
+
+/// Share of the pheromone of a chosen value that evaporates towards the
+/// initial pheromone every time an ant chooses it, which makes the
+/// following ants of the same iteration explore other values.
+const LOCAL_EVAPORATION: f32 = 0.1;
+
+/// Share of the pheromone that the best solution adds, which evaporates in
+/// every iteration so that better solutions found later can take over.
+const BEST_EVAPORATION: f32 = 0.005;
+
+/// The pheromone of every value of every field.
+struct Pheromones {
+    size: usize,
+    initial: f32,
+    values: Vec<f32>,
+}
+
+impl Pheromones {
+    fn new(size: u8) -> Pheromones {
+        let size = usize::from(size);
+        let initial = 1.0 / (size * size) as f32;
+        Pheromones {
+            size,
+            initial,
+            values: vec![initial; size * size * size],
+        }
+    }
+
+    fn index(&self, field: &Field, value: u8) -> usize {
+        (usize::from(field.row) * self.size + usize::from(field.column)) * self.size
+            + usize::from(value)
+            - 1
+    }
+
+    fn get(&self, field: &Field, value: u8) -> f32 {
+        self.values[self.index(field, value)]
+    }
+
+    /// Moves the pheromone of a value towards `target` by the given share.
+    fn update(&mut self, field: &Field, value: u8, share: f32, target: f32) {
+        let index = self.index(field, value);
+        self.values[index] = (1.0 - share) * self.values[index] + share * target;
+    }
+
+    /// Moves the pheromone of the values of the best solution towards the
+    /// pheromone that it adds.
+    fn reinforce(&mut self, best: &CandidateGrid, fields: &[Field], share: f32, target: f32) {
+        for field in fields.iter() {
+            let value = best.grid().get(field);
+            if value != 0 {
+                self.update(field, value, share, target);
+            }
+        }
+    }
+}
+
+/// Solves sudokus with ant colony optimization as described by Lloyd and
+/// Amos (2019). In every iteration each ant starts at a random field and
+/// visits all fields in row major order. It places one of the candidates of
+/// every empty field, chosen by the pheromone of the value, and propagates
+/// constraints afterwards like `Propagation`. Fields without candidates are
+/// left empty. The ant that filled the most fields adds pheromone to its
+/// values if it is the best one so far.
+pub struct Aco {
+    max_tries: u32,
+    tries: u32,
+    outcome: Option<Outcome>,
+    ants: usize,
+    /// Share of the pheromone that evaporates when the best solution adds
+    /// its pheromone.
+    evaporation: f32,
+    rng: SeededRng,
+    cancel: CancellationToken,
+    observer: Option<Observer>,
+}
+
+impl Aco {
+    /// Creates a colony of at least one ant. The evaporation is the share of
+    /// the pheromone that the best solution replaces in every iteration.
+    pub fn new(max_tries: u32, ants: usize, evaporation: f32, seed: Option<u64>) -> Aco {
+        Aco {
+            max_tries,
+            tries: 0,
+            outcome: None,
+            ants,
+            evaporation,
+            rng: SeededRng::new(seed),
+            cancel: CancellationToken::new(),
+            observer: None,
+        }
+    }
+
+    /// Chooses a value among the candidates of a field, either the one with
+    /// the most pheromone or a random one with a probability proportional
+    /// to its pheromone.
+    fn choose_value(&mut self, field: &Field, values: ValueSet, pheromones: &Pheromones) -> u8 {
+        let pheromone = |v: &u8| pheromones.get(field, *v);
+        if self.rng.gen::<f32>() < GREEDINESS {
+            return values
+                .iter()
+                .max_by(|a, b| pheromone(a).partial_cmp(&pheromone(b)).unwrap())
+                .unwrap();
+        }
+        let total: f32 = values.iter().map(|v| pheromone(&v)).sum();
+        let mut threshold = self.rng.gen::<f32>() * total;
+        for value in values.iter() {
+            threshold -= pheromone(&value);
+            if threshold <= 0.0 {
+                return value;
+            }
+        }
+        // Rounding errors may leave a small rest.
+        values.iter().last().unwrap()
+    }
+
+    /// Lets a single ant fill the fields starting from the propagated
+    /// candidates of the sudoku.
+    fn run_ant(
+        &mut self,
+        start: &CandidateGrid,
+        fields: &[Field],
+        pheromones: &mut Pheromones,
+    ) -> CandidateGrid {
+        let mut candidates = start.clone();
+        let offset = self.rng.gen_range(0, fields.len());
+        for i in 0..fields.len() {
+            let field = &fields[(offset + i) % fields.len()];
+            let values = candidates.get(field);
+            if candidates.grid().get(field) != 0 || values.is_empty() {
+                continue;
+            }
+            let value = match values.len() {
+                1 => values.first().unwrap(),
+                _ => self.choose_value(field, values, pheromones),
+            };
+            candidates.place(field, value);
+            // Fields without candidates stay empty, as in a partial solution.
+            Propagation::propagate_past_contradictions(&mut candidates);
+            pheromones.update(field, value, LOCAL_EVAPORATION, pheromones.initial);
+        }
+        candidates
+    }
+
+    fn count_filled(candidates: &CandidateGrid, fields: &[Field]) -> usize {
+        fields
+            .iter()
+            .filter(|f| candidates.grid().get(f) != 0)
+            .count()
+    }
+}
+
+impl Solver for Aco {
+    fn get_outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// Returns the number of iterations.
+    fn get_tries(&self) -> u32 {
+        self.tries
+    }
+
+    fn get_seed(&self) -> Option<u64> {
+        Some(self.rng.seed())
+    }
+
+    fn solve(&mut self, mut sudoku: Sudoku) -> Sudoku {
+        let mut start = CandidateGrid::from_grid(&sudoku.grid);
+        if !Propagation::propagate(&mut start) {
+            // A field has no candidates, the sudoku cannot be solved.
+            self.outcome = Some(Outcome::Unsolvable);
+            return sudoku;
+        }
+        let fields = sudoku.grid.mutable_fields.clone();
+        let mut pheromones = Pheromones::new(sudoku.grid.size());
+        let mut best = start.clone();
+        let mut best_filled = Aco::count_filled(&best, &fields);
+        // The pheromone that the best solution adds, which grows with the
+        // number of filled fields.
+        let mut best_pheromone = 0.0;
+
+        while best_filled < fields.len() {
+            if self.tries >= self.max_tries || self.cancel.is_cancelled() {
+                break;
+            }
+
+            let mut iteration_best = None;
+            let mut iteration_filled = 0;
+            for _ in 0..self.ants {
+                let ant = self.run_ant(&start, &fields, &mut pheromones);
+                let filled = Aco::count_filled(&ant, &fields);
+                if iteration_best.is_none() || filled > iteration_filled {
+                    iteration_best = Some(ant);
+                    iteration_filled = filled;
+                }
+                if filled == fields.len() {
+                    break;
+                }
+            }
+
+            let pheromone = fields.len() as f32 / (fields.len() - iteration_filled).max(1) as f32;
+            if pheromone > best_pheromone || iteration_filled == fields.len() {
+                best = iteration_best.unwrap();
+                best_filled = iteration_filled;
+                best_pheromone = pheromone;
+            }
+            pheromones.reinforce(&best, &fields, self.evaporation, best_pheromone);
+            best_pheromone *= 1.0 - BEST_EVAPORATION;
+
+            self.tries += 1;
+            if let Some(observer) = &mut self.observer {
+                if observer.is_due(self.tries) {
+                    observer.notify(&Progress {
+                        tries: self.tries,
+                        energy: Some((fields.len() - best_filled) as f32),
+                        ..Progress::default()
+                    });
+                }
+            }
+        }
+
+        for field in fields.iter() {
+            sudoku.grid.set(field, best.grid().get(field));
+        }
+        self.outcome = Some(if sudoku.is_done() {
+            Outcome::Solved
+        } else {
+            Outcome::stopped(&self.cancel)
+        });
+        sudoku
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    fn set_observer(&mut self, observer: Observer) {
+        self.observer = Some(observer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_reinforce_the_values_of_the_best_solution() {
+        let s: Sudoku = "12.434.221434321".parse().unwrap();
+        let fields = s.grid.mutable_fields.clone();
+        let mut best = CandidateGrid::from_grid(&s.grid);
+        assert!(Propagation::propagate(&mut best));
+        let mut pheromones = Pheromones::new(4);
+        pheromones.reinforce(&best, &fields, 0.5, 1.0);
+        assert!(pheromones.get(&Field::new(0, 2), 3) > pheromones.initial);
+        assert!(pheromones.get(&Field::new(1, 2), 1) > pheromones.initial);
+        assert!((pheromones.get(&Field::new(0, 2), 1) - pheromones.initial).abs() < 1e-6);
+        assert!((pheromones.get(&Field::new(1, 2), 3) - pheromones.initial).abs() < 1e-6);
+    }
+
+    #[test]
+    fn it_should_stop_on_unsolvable_sudoku() {
+        let s: Sudoku = "1.3..4.......3.4".parse().unwrap();
+        let mut solver = Aco::new(100, 10, 0.9, Some(1));
+        solver.solve(s);
+        assert_eq!(solver.get_outcome(), Some(Outcome::Unsolvable));
+        assert_eq!(solver.get_tries(), 0);
+    }
+
+    #[test]
+    fn it_should_move_pheromone_towards_target() {
+        let mut pheromones = Pheromones::new(9);
+        let field = Field::new(2, 3);
+        assert!((pheromones.get(&field, 5) - 1.0 / 81.0).abs() < 1e-6);
+        pheromones.update(&field, 5, 0.5, 1.0);
+        assert!((pheromones.get(&field, 5) - (0.5 / 81.0 + 0.5)).abs() < 1e-6);
+        assert!((pheromones.get(&field, 4) - 1.0 / 81.0).abs() < 1e-6);
+        assert!((pheromones.get(&Field::new(3, 2), 5) - 1.0 / 81.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,183 @@
+/// Number of tries after which the adaptive cooling schedule adjusts the
+/// temperature.
+const ADAPTIVE_WINDOW: u32 = 100;
+
+/// Distance parameter of the adaptive cooling schedule, smaller values cool
+/// down more slowly.
+const ADAPTIVE_DISTANCE: f32 = 0.1;
+
+/// Selects how the temperature of a Montecarlo simulation decreases.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cooling {
+    /// The start temperature is kept for the whole simulation.
+    Constant,
+    /// The temperature decreases by the same factor in every step.
+    Geometric,
+    /// The temperature decreases by the same amount in every step.
+    Linear,
+    /// The temperature decreases according to the standard deviation of the
+    /// energy, i.e. slowly while the energy changes a lot and fast
+    /// otherwise (Aarts and van Laarhoven).
+    Adaptive,
+}
+
+/// A simulated annealing schedule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Schedule {
+    pub cooling: Cooling,
+    pub start_temperature: f32,
+    pub end_temperature: f32,
+    /// Number of tries in which the geometric and linear schedules cool down
+    /// from the start to the end temperature.
+    pub steps: u32,
+    /// Number of tries without a new lowest energy after which the
+    /// temperature is reset to the start temperature. `None` disables
+    /// reheating.
+    pub reheat_after: Option<u32>,
+}
+
+impl Schedule {
+    /// Creates a schedule with a fixed temperature.
+    pub fn constant(temperature: f32) -> Schedule {
+        Schedule {
+            cooling: Cooling::Constant,
+            start_temperature: temperature,
+            end_temperature: temperature,
+            steps: 1,
+            reheat_after: None,
+        }
+    }
+}
+
+/// The temperature of a running simulation that follows a schedule.
+pub struct Temperature {
+    schedule: Schedule,
+    current: f32,
+    /// Tries since the start or the last reheating.
+    step: u32,
+    lowest_energy: f32,
+    /// Tries since the lowest energy has been reached.
+    stale: u32,
+    /// Sum and sum of squares of the energies of the current adaptive window.
+    window: (f32, f32),
+}
+
+impl Temperature {
+    pub fn new(schedule: Schedule) -> Temperature {
+        Temperature {
+            schedule,
+            current: schedule.start_temperature,
+            step: 0,
+            lowest_energy: f32::INFINITY,
+            stale: 0,
+            window: (0.0, 0.0),
+        }
+    }
+
+    pub fn get(&self) -> f32 {
+        self.current
+    }
+
+    /// Updates the temperature after a try that resulted in the given
+    /// energy.
+    pub fn update(&mut self, energy: f32) {
+        if energy < self.lowest_energy {
+            self.lowest_energy = energy;
+            self.stale = 0;
+        } else {
+            self.stale += 1;
+        }
+        if self.schedule.reheat_after.is_some_and(|n| self.stale >= n) {
+            self.current = self.schedule.start_temperature;
+            self.step = 0;
+            self.stale = 0;
+            self.window = (0.0, 0.0);
+            return;
+        }
+
+        self.step += 1;
+        let (start, end) = (
+            self.schedule.start_temperature,
+            self.schedule.end_temperature,
+        );
+        let progress = (self.step as f32 / self.schedule.steps as f32).min(1.0);
+        match self.schedule.cooling {
+            Cooling::Constant => {}
+            Cooling::Geometric => self.current = start * (end / start).powf(progress),
+            Cooling::Linear => self.current = start + (end - start) * progress,
+            Cooling::Adaptive => {
+                self.window.0 += energy;
+                self.window.1 += energy * energy;
+                if self.step.is_multiple_of(ADAPTIVE_WINDOW) {
+                    let n = ADAPTIVE_WINDOW as f32;
+                    let mean = self.window.0 / n;
+                    let deviation = (self.window.1 / n - mean * mean).max(0.0).sqrt();
+                    if deviation > 0.0 {
+                        let t = self.current;
+                        self.current =
+                            t / (1.0 + t * (1.0 + ADAPTIVE_DISTANCE).ln() / (3.0 * deviation));
+                    }
+                    self.current = self.current.max(end);
+                    self.window = (0.0, 0.0);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(cooling: Cooling) -> Schedule {
+        Schedule {
+            cooling,
+            start_temperature: 1.0,
+            end_temperature: 0.01,
+            steps: 100,
+            reheat_after: None,
+        }
+    }
+
+    #[test]
+    fn it_should_cool_down() {
+        let mut geometric = Temperature::new(schedule(Cooling::Geometric));
+        let mut linear = Temperature::new(schedule(Cooling::Linear));
+        for _ in 0..50 {
+            geometric.update(10.0);
+            linear.update(10.0);
+        }
+        assert!((geometric.get() - 0.1).abs() < 1e-5);
+        assert!((linear.get() - 0.505).abs() < 1e-5);
+        for _ in 0..100 {
+            geometric.update(10.0);
+            linear.update(10.0);
+        }
+        assert!((geometric.get() - 0.01).abs() < 1e-5);
+        assert!((linear.get() - 0.01).abs() < 1e-5);
+    }
+
+    #[test]
+    fn it_should_adapt_to_energy_deviation() {
+        let mut temperature = Temperature::new(schedule(Cooling::Adaptive));
+        for i in 0..ADAPTIVE_WINDOW {
+            temperature.update(f32::from(i as u8 & 1));
+        }
+        assert!(temperature.get() < 1.0);
+        assert!(temperature.get() > 0.01);
+    }
+
+    #[test]
+    fn it_should_reheat_on_plateau() {
+        let mut temperature = Temperature::new(Schedule {
+            reheat_after: Some(10),
+            ..schedule(Cooling::Linear)
+        });
+        for _ in 0..10 {
+            temperature.update(5.0);
+        }
+        assert!(temperature.get() < 1.0);
+        temperature.update(5.0);
+        assert_eq!(temperature.get(), 1.0);
+    }
+}
@@ -1,9 +1,12 @@
 use super::super::Sudoku;
-use super::Solver;
+use super::{CancellationToken, Observer, Outcome, Progress, Solver};
 
 pub struct Backtracing {
     max_tries: u32,
     tries: u32,
+    outcome: Option<Outcome>,
+    cancel: CancellationToken,
+    observer: Option<Observer>,
 }
 
 impl Backtracing {
@@ -11,13 +14,16 @@ impl Backtracing {
         Backtracing {
             max_tries,
             tries: 0,
+            outcome: None,
+            cancel: CancellationToken::new(),
+            observer: None,
         }
     }
 }
 
 impl Solver for Backtracing {
-    fn is_success(&self) -> bool {
-        self.tries < self.max_tries
+    fn get_outcome(&self) -> Option<Outcome> {
+        self.outcome
     }
 
     fn get_tries(&self) -> u32 {
@@ -28,29 +34,101 @@ impl Solver for Backtracing {
     /// [Backtracing](https://en.wikipedia.org/wiki/Sudoku_solving_algorithms#Backtracking)
     /// algorithm.
     /// This method is guaranteed to find a solution if the sudoku is valid.
+    /// If the sudoku has no solution the search stops once all guesses of the
+    /// first field have been tried.
     fn solve(&mut self, mut sudoku: Sudoku) -> Sudoku {
+        let mutable_fields = sudoku.grid.mutable_fields.clone();
         let mut index = 0;
 
-        while !sudoku.is_done() {
-            let field = sudoku.grid.mutable_fields[index].clone();
-            let val = sudoku.grid.get(&field);
-            let guesses = sudoku.get_field_guesses(&field);
-            let next_guesses: Vec<u8> = guesses.into_iter().filter(|v| v > &val).collect();
-            if next_guesses.len() == 0 {
-                // No more guesses available
-                // Go back one step and use next guess there
-                sudoku.grid.set(&field, 0);
-                index -= 1;
-            } else {
-                sudoku.grid.set(&field, next_guesses[0]);
-                index += 1;
+        while index < mutable_fields.len()
+            && self.tries < self.max_tries
+            && !self.cancel.is_cancelled()
+        {
+            let field = &mutable_fields[index];
+            let val = sudoku.grid.get(field);
+            match sudoku.grid.get_candidates(field).next_after(val) {
+                Some(guess) => {
+                    sudoku.grid.set(field, guess);
+                    index += 1;
+                }
+                None => {
+                    // No more guesses available
+                    // Go back one step and use next guess there
+                    sudoku.grid.set(field, 0);
+                    if index == 0 {
+                        break;
+                    }
+                    index -= 1;
+                }
             }
             self.tries += 1;
-            if self.tries >= self.max_tries {
-                break;
+            if let Some(observer) = &mut self.observer {
+                if observer.is_due(self.tries) {
+                    observer.notify(&Progress {
+                        tries: self.tries,
+                        depth: Some(index),
+                        ..Progress::default()
+                    });
+                }
             }
         }
 
+        self.outcome = Some(if sudoku.is_done() {
+            Outcome::Solved
+        } else {
+            Outcome::after_search(self.tries, self.max_tries, &self.cancel)
+        });
         sudoku
     }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    fn set_observer(&mut self, observer: Observer) {
+        self.observer = Some(observer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn it_should_solve_sudoku() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku1.txt").unwrap();
+        let mut solver = Backtracing::new(100_000);
+        let s = solver.solve(s);
+        assert!(solver.is_success());
+        assert_eq!(solver.get_tries(), 63);
+        assert_eq!(s.grid.get_row(0), vec![4, 3, 5, 2, 6, 9, 7, 8, 1]);
+    }
+
+    #[test]
+    fn it_should_stop_on_unsolvable_sudoku() {
+        let s: Sudoku = "1.3..4.......3.4".parse().unwrap();
+        let mut solver = Backtracing::new(100_000);
+        solver.solve(s);
+        assert_eq!(solver.get_outcome(), Some(Outcome::Unsolvable));
+        assert!(solver.get_tries() < 100_000);
+    }
+
+    #[test]
+    fn it_should_report_progress() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku1.txt").unwrap();
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut solver = Backtracing::new(100_000);
+        let sink = Arc::clone(&events);
+        solver.set_observer(Observer::new(20, move |progress| {
+            sink.lock().unwrap().push(progress.clone())
+        }));
+        solver.solve(s);
+        let events = events.lock().unwrap();
+        let tries: Vec<u32> = events.iter().map(|progress| progress.tries).collect();
+        assert_eq!(tries, vec![20, 40, 60]);
+        assert!(events.iter().all(|progress| progress.depth.is_some()));
+    }
 }
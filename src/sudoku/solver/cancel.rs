@@ -0,0 +1,63 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// A flag that asks a running solver to stop. Clones share the same flag, so
+/// a token can be handed to solvers on other threads and cancelled from
+/// anywhere. Solvers check the token in their search loop and return the
+/// unsolved sudoku once it has been cancelled.
+/// A token with a deadline cancels itself once the deadline has passed.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Creates a token that is cancelled at the given point in time at the
+    /// latest.
+    pub fn with_deadline(deadline: Instant) -> CancellationToken {
+        CancellationToken {
+            deadline: Some(deadline),
+            ..CancellationToken::default()
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn it_should_share_cancellation_between_clones() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_cancelled());
+        token.cancel();
+        assert!(clone.is_cancelled());
+    }
+
+    #[test]
+    fn it_should_cancel_after_deadline() {
+        let token = CancellationToken::with_deadline(Instant::now() + Duration::from_secs(60));
+        assert!(!token.is_cancelled());
+        let token = CancellationToken::with_deadline(Instant::now());
+        assert!(token.is_cancelled());
+    }
+}
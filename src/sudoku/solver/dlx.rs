@@ -0,0 +1,318 @@
+use super::super::{Field, Sudoku};
+use super::{CancellationToken, Observer, Outcome, Progress, Solver};
+
+/// The exact cover matrix of a sudoku as a toroidal doubly linked list, see
+/// [Dancing Links](https://en.wikipedia.org/wiki/Dancing_Links).
+/// Node 0 is the root, nodes `1..=columns` are the column headers and all
+/// other nodes are the ones of the matrix. Nodes are linked by their indices.
+struct Links {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    column: Vec<usize>,
+    /// The matrix row of each node.
+    row: Vec<usize>,
+    /// The number of nodes of each column, indexed by its header.
+    size: Vec<usize>,
+}
+
+impl Links {
+    fn new(columns: usize) -> Links {
+        let headers = columns + 1;
+        Links {
+            left: (0..headers).map(|i| (i + headers - 1) % headers).collect(),
+            right: (0..headers).map(|i| (i + 1) % headers).collect(),
+            up: (0..headers).collect(),
+            down: (0..headers).collect(),
+            column: (0..headers).collect(),
+            row: vec![0; headers],
+            size: vec![0; headers],
+        }
+    }
+
+    /// Appends a matrix row with a node in each of the given columns.
+    fn add_row(&mut self, row: usize, columns: &[usize]) {
+        let first = self.left.len();
+        for (i, c) in columns.iter().enumerate() {
+            let header = c + 1;
+            let node = first + i;
+            self.left.push(if i == 0 {
+                first + columns.len() - 1
+            } else {
+                node - 1
+            });
+            self.right.push(if i == columns.len() - 1 {
+                first
+            } else {
+                node + 1
+            });
+            self.up.push(self.up[header]);
+            self.down.push(header);
+            self.column.push(header);
+            self.row.push(row);
+            let last = self.up[header];
+            self.down[last] = node;
+            self.up[header] = node;
+            self.size[header] += 1;
+        }
+    }
+
+    /// Removes a column and all rows that have a node in it.
+    fn cover(&mut self, header: usize) {
+        self.right[self.left[header]] = self.right[header];
+        self.left[self.right[header]] = self.left[header];
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                self.down[self.up[j]] = self.down[j];
+                self.up[self.down[j]] = self.up[j];
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    /// Reverts `cover` in the reverse order.
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                self.down[self.up[j]] = j;
+                self.up[self.down[j]] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        self.right[self.left[header]] = header;
+        self.left[self.right[header]] = header;
+    }
+
+    /// Returns the uncovered column with the fewest nodes or `None` if all
+    /// columns are covered.
+    fn choose_column(&self) -> Option<usize> {
+        let mut best: Option<usize> = None;
+        let mut c = self.right[0];
+        while c != 0 {
+            if best.is_none_or(|b| self.size[c] < self.size[b]) {
+                best = Some(c);
+            }
+            c = self.right[c];
+        }
+        best
+    }
+}
+
+pub struct Dlx {
+    max_tries: u32,
+    tries: u32,
+    outcome: Option<Outcome>,
+    cancel: CancellationToken,
+    observer: Option<Observer>,
+}
+
+impl Dlx {
+    pub fn new(max_tries: u32) -> Dlx {
+        Dlx {
+            max_tries,
+            tries: 0,
+            outcome: None,
+            cancel: CancellationToken::new(),
+            observer: None,
+        }
+    }
+
+    /// Returns the four constraint columns that are satisfied by a value in
+    /// a field: the field itself and the value in its row, column and
+    /// parcel.
+    fn columns(sudoku: &Sudoku, field: &Field, value: u8) -> [usize; 4] {
+        let n = usize::from(sudoku.grid.size());
+        let r = usize::from(field.row);
+        let c = usize::from(field.column);
+        let p = usize::from(sudoku.grid.get_parcel_index(field));
+        let v = usize::from(value) - 1;
+        [
+            r * n + c,
+            n * n + r * n + v,
+            2 * n * n + c * n + v,
+            3 * n * n + p * n + v,
+        ]
+    }
+
+    /// Runs Knuth's Algorithm X and collects the chosen rows in `solution`.
+    /// Every chosen row counts as a try.
+    fn search(&mut self, links: &mut Links, solution: &mut Vec<usize>) -> bool {
+        let header = match links.choose_column() {
+            Some(header) => header,
+            None => return true,
+        };
+        if links.size[header] == 0 {
+            return false;
+        }
+
+        links.cover(header);
+        let mut r = links.down[header];
+        while r != header && self.tries < self.max_tries && !self.cancel.is_cancelled() {
+            self.tries += 1;
+            solution.push(links.row[r]);
+            if let Some(observer) = &mut self.observer {
+                if observer.is_due(self.tries) {
+                    observer.notify(&Progress {
+                        tries: self.tries,
+                        depth: Some(solution.len()),
+                        ..Progress::default()
+                    });
+                }
+            }
+            let mut j = links.right[r];
+            while j != r {
+                links.cover(links.column[j]);
+                j = links.right[j];
+            }
+            if self.search(links, solution) {
+                return true;
+            }
+            let mut j = links.left[r];
+            while j != r {
+                links.uncover(links.column[j]);
+                j = links.left[j];
+            }
+            solution.pop();
+            r = links.down[r];
+        }
+        links.uncover(header);
+        false
+    }
+}
+
+impl Solver for Dlx {
+    fn get_outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    fn get_tries(&self) -> u32 {
+        self.tries
+    }
+
+    /// Solves the sudoku as an exact cover problem with
+    /// [Dancing Links](https://en.wikipedia.org/wiki/Dancing_Links).
+    /// Each row of the matrix stands for a value in a field and each column
+    /// for a constraint, i.e. a field that needs a value and a value that is
+    /// needed in a row, column or parcel. The columns of the given values are
+    /// covered up front, the remaining ones by the search, which always
+    /// branches on the column with the fewest rows.
+    fn solve(&mut self, mut sudoku: Sudoku) -> Sudoku {
+        let n = sudoku.grid.size();
+        let mut links = Links::new(4 * usize::from(n) * usize::from(n));
+        let mut fields: Vec<(Field, u8)> = vec![];
+        for r in 0..n {
+            for c in 0..n {
+                for value in 1..=n {
+                    let field = Field::new(r, c);
+                    links.add_row(fields.len(), &Dlx::columns(&sudoku, &field, value));
+                    fields.push((field, value));
+                }
+            }
+        }
+
+        // Cover the constraints of the given values. A constraint that is
+        // already covered means that the sudoku contains a conflict.
+        let mut covered = vec![false; links.size.len()];
+        let mut consistent = true;
+        for r in 0..n {
+            for c in 0..n {
+                let field = Field::new(r, c);
+                let value = sudoku.grid.get(&field);
+                if value == 0 {
+                    continue;
+                }
+                for column in Dlx::columns(&sudoku, &field, value).iter() {
+                    let header = column + 1;
+                    consistent &= !covered[header];
+                    if !covered[header] {
+                        links.cover(header);
+                        covered[header] = true;
+                    }
+                }
+            }
+        }
+
+        let mut solution = vec![];
+        if consistent && self.search(&mut links, &mut solution) {
+            for row in solution {
+                let (field, value) = &fields[row];
+                sudoku.grid.set(field, *value);
+            }
+        }
+
+        self.outcome = Some(if sudoku.is_done() {
+            Outcome::Solved
+        } else {
+            Outcome::after_search(self.tries, self.max_tries, &self.cancel)
+        });
+        sudoku
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    fn set_observer(&mut self, observer: Observer) {
+        self.observer = Some(observer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_solve_sudoku() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku1.txt").unwrap();
+        let mut solver = Dlx::new(100_000);
+        let s = solver.solve(s);
+        assert!(solver.is_success());
+        assert_eq!(s.grid.get_row(0), vec![4, 3, 5, 2, 6, 9, 7, 8, 1]);
+    }
+
+    #[test]
+    fn it_should_solve_hard_sudoku() {
+        let s: Sudoku =
+            "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......"
+                .parse()
+                .unwrap();
+        let mut solver = Dlx::new(100_000);
+        let s = solver.solve(s);
+        assert!(solver.is_success());
+        assert_eq!(s.grid.get_row(0), vec![4, 1, 7, 3, 6, 9, 8, 2, 5]);
+    }
+
+    #[test]
+    fn it_should_solve_rectangular_parcels() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku12.txt").unwrap();
+        let mut solver = Dlx::new(100_000);
+        let s = solver.solve(s);
+        assert!(solver.is_success());
+        assert_eq!(s.grid.size(), 12);
+    }
+
+    #[test]
+    fn it_should_fail_on_unsolvable_sudoku() {
+        let s: Sudoku = "1.3..4.......3.4".parse().unwrap();
+        let mut solver = Dlx::new(100_000);
+        solver.solve(s);
+        assert_eq!(solver.get_outcome(), Some(Outcome::Unsolvable));
+
+        let s: Sudoku = "11..............".parse().unwrap();
+        let mut solver = Dlx::new(100_000);
+        solver.solve(s);
+        assert_eq!(solver.get_outcome(), Some(Outcome::Unsolvable));
+        assert_eq!(solver.get_tries(), 0);
+    }
+}
@@ -0,0 +1,174 @@
+use rand::Rng;
+
+use super::super::Sudoku;
+use super::montecarlo::Chain;
+use super::{CancellationToken, Observer, Outcome, Progress, SeededRng, Solver};
+
+/// Number of random individuals that compete to become a parent, the one
+/// with the lowest energy wins.
+const TOURNAMENT_SIZE: usize = 3;
+
+/// Solves sudokus with a genetic algorithm. Every individual of the
+/// population fills the parcels with permutations of their missing values,
+/// like the states of a Montecarlo simulation, and its fitness is the energy.
+/// Children take every parcel from one of two parents chosen by tournament
+/// selection and are mutated by swapping two values of a parcel.
+pub struct Genetic {
+    max_tries: u32,
+    tries: u32,
+    outcome: Option<Outcome>,
+    population: usize,
+    mutation_rate: f32,
+    /// Number of the best individuals that are kept unchanged in the next
+    /// generation.
+    elitism: usize,
+    rng: SeededRng,
+    cancel: CancellationToken,
+    observer: Option<Observer>,
+}
+
+impl Genetic {
+    /// Creates a genetic algorithm. The population needs at least two
+    /// individuals and the elitism must be less than the population, so
+    /// that at least one child is created per generation.
+    pub fn new(
+        max_tries: u32,
+        population: usize,
+        mutation_rate: f32,
+        elitism: usize,
+        seed: Option<u64>,
+    ) -> Genetic {
+        Genetic {
+            max_tries,
+            tries: 0,
+            outcome: None,
+            population,
+            mutation_rate,
+            elitism,
+            rng: SeededRng::new(seed),
+            cancel: CancellationToken::new(),
+            observer: None,
+        }
+    }
+
+    /// Selects a parent by tournament selection.
+    fn select<'a>(&mut self, population: &'a [Chain]) -> &'a Chain {
+        (0..TOURNAMENT_SIZE)
+            .map(|_| &population[self.rng.gen_range(0, population.len())])
+            .min_by(|a, b| a.energy.partial_cmp(&b.energy).unwrap())
+            .unwrap()
+    }
+}
+
+impl Solver for Genetic {
+    fn get_outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// Returns the number of generations.
+    fn get_tries(&self) -> u32 {
+        self.tries
+    }
+
+    fn get_seed(&self) -> Option<u64> {
+        Some(self.rng.seed())
+    }
+
+    fn solve(&mut self, sudoku: Sudoku) -> Sudoku {
+        let origin = Chain::new(sudoku);
+        if origin.is_frozen() {
+            // Nothing can be changed, the sudoku is either solved already or
+            // it cannot be solved.
+            self.outcome = Some(if origin.is_done() {
+                Outcome::Solved
+            } else {
+                Outcome::Unsolvable
+            });
+            return origin.sudoku;
+        }
+        let mut population: Vec<Chain> = (0..self.population)
+            .map(|_| {
+                let mut individual = origin.clone();
+                individual.shuffle(&mut self.rng);
+                individual
+            })
+            .collect();
+
+        loop {
+            population.sort_by(|a, b| a.energy.partial_cmp(&b.energy).unwrap());
+            if population[0].is_done() {
+                self.outcome = Some(Outcome::Solved);
+                return population.swap_remove(0).sudoku;
+            }
+            if self.tries >= self.max_tries || self.cancel.is_cancelled() {
+                self.outcome = Some(Outcome::stopped(&self.cancel));
+                return population.swap_remove(0).sudoku;
+            }
+
+            let mut next: Vec<Chain> = population[..self.elitism].to_vec();
+            while next.len() < self.population {
+                let mother = self.select(&population);
+                let father = self.select(&population);
+                let mut child = mother.crossover(father, &mut self.rng);
+                if self.rng.gen::<f32>() < self.mutation_rate {
+                    child.mutate(&mut self.rng);
+                }
+                next.push(child);
+            }
+            population = next;
+
+            self.tries += 1;
+            if let Some(observer) = &mut self.observer {
+                if observer.is_due(self.tries) {
+                    let energy = population.iter().map(|c| c.energy).fold(f32::MAX, f32::min);
+                    observer.notify(&Progress {
+                        tries: self.tries,
+                        energy: Some(energy),
+                        ..Progress::default()
+                    });
+                }
+            }
+        }
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    fn set_observer(&mut self, observer: Observer) {
+        self.observer = Some(observer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn it_should_keep_the_best_individual() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku3.txt").unwrap();
+        let energies = Arc::new(Mutex::new(vec![]));
+        // Every child is mutated, so only elitism keeps the best energy.
+        let mut solver = Genetic::new(200, 20, 1.0, 1, Some(1));
+        let sink = Arc::clone(&energies);
+        solver.set_observer(Observer::new(1, move |progress| {
+            sink.lock().unwrap().push(progress.energy.unwrap())
+        }));
+        solver.solve(s);
+        let energies = energies.lock().unwrap();
+        assert!(energies.len() > 1);
+        assert!(energies.windows(2).all(|pair| pair[1] <= pair[0]));
+    }
+
+    #[test]
+    fn it_should_return_the_best_individual_on_failure() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku3.txt").unwrap();
+        let mut solver = Genetic::new(5, 10, 0.5, 1, Some(1));
+        let s = solver.solve(s);
+        assert_eq!(solver.get_outcome(), Some(Outcome::Exhausted));
+        assert!(s.grid.mutable_fields.iter().all(|f| s.grid.get(f) != 0));
+    }
+}
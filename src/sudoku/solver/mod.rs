@@ -1,13 +1,94 @@
+pub mod aco;
+pub mod annealing;
 pub mod backtracing;
+pub mod cancel;
+pub mod dlx;
+pub mod genetic;
 pub mod montecarlo;
+pub mod portfolio;
+pub mod progress;
+pub mod propagation;
+pub mod rng;
+pub mod sat;
+pub mod tabu;
+pub mod tempering;
 
 use super::super::sudoku::Sudoku;
 
+/// How a run of a solver ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Solved,
+    /// The solver proved that the sudoku has no solution.
+    Unsolvable,
+    /// The solver used up its tries before it found a solution or proved
+    /// that there is none.
+    Exhausted,
+    /// The solver has been cancelled or its deadline has passed.
+    Cancelled,
+}
+
+impl Outcome {
+    /// Returns the outcome of a run that stopped before it found a solution
+    /// or proved that there is none.
+    pub fn stopped(cancel: &CancellationToken) -> Outcome {
+        if cancel.is_cancelled() {
+            Outcome::Cancelled
+        } else {
+            Outcome::Exhausted
+        }
+    }
+
+    /// Returns the outcome of an exhaustive search that did not find a
+    /// solution. Such a search proves that there is none, unless it has been
+    /// stopped early.
+    pub fn after_search(tries: u32, max_tries: u32, cancel: &CancellationToken) -> Outcome {
+        if tries < max_tries && !cancel.is_cancelled() {
+            Outcome::Unsolvable
+        } else {
+            Outcome::stopped(cancel)
+        }
+    }
+}
+
 pub trait Solver {
-    fn is_success(&self) -> bool;
+    /// Returns how the last run ended, `None` if the solver has not run yet.
+    fn get_outcome(&self) -> Option<Outcome>;
+    fn is_success(&self) -> bool {
+        self.get_outcome() == Some(Outcome::Solved)
+    }
     fn get_tries(&self) -> u32;
     fn solve(&mut self, sudoku: Sudoku) -> Sudoku;
+    /// Sets the token that is checked while solving. Once the token has been
+    /// cancelled or its deadline has passed, the solver stops and reports a
+    /// failure.
+    fn set_cancellation_token(&mut self, token: CancellationToken);
+    /// Sets the observer that receives the progress of the solver while
+    /// solving.
+    fn set_observer(&mut self, observer: Observer);
+    /// Returns the name of the algorithm that found the solution if the
+    /// solver combines several algorithms.
+    fn get_winner(&self) -> Option<&str> {
+        None
+    }
+    /// Returns the seed of the random numbers if the solver is randomized.
+    /// Running the solver with the same seed again reproduces the run.
+    fn get_seed(&self) -> Option<u64> {
+        None
+    }
 }
 
+pub use aco::Aco;
+pub use annealing::{Cooling, Schedule};
 pub use backtracing::Backtracing;
+pub use cancel::CancellationToken;
+pub use dlx::Dlx;
+pub use genetic::Genetic;
 pub use montecarlo::Montecarlo;
+pub use portfolio::{Portfolio, SolverFactory};
+pub use progress::{Observer, Progress};
+pub use propagation::{CellOrder, Propagation, ValueOrder};
+pub use rng::SeededRng;
+pub use sat::Sat;
+pub use tabu::Tabu;
+pub use tempering::Tempering;
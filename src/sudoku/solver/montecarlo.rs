@@ -1,10 +1,11 @@
 use itertools::Itertools;
 use rand::distributions::{Distribution, Uniform};
-use rand::seq::SliceRandom;
+use rand::seq::{index, SliceRandom};
 use rand::Rng;
 
-use super::super::Sudoku;
-use super::Solver;
+use super::super::{Field, Sudoku, Unit};
+use super::annealing::Temperature;
+use super::{CancellationToken, Observer, Outcome, Progress, Schedule, SeededRng, Solver};
 
 pub enum EnergyDimension {
     Row,
@@ -14,11 +15,12 @@ pub enum EnergyDimension {
 
 impl Sudoku {
     /// Calculates the current energy of the system.
-    /// The energy is defined as 3*n**4 minus the sum of the number of unique
-    /// elements in each row, column and parcel.
+    /// The energy is defined as 3*n**2 minus the sum of the number of unique
+    /// elements in each row, column and parcel, where n is the size of the
+    /// sudoku.
     fn calc_energy(&self) -> f32 {
-        let n = 3;
-        let energy_max = f32::from(3 * i16::pow(n, 4));
+        let n = u16::from(self.grid.size());
+        let energy_max = f32::from(3 * n * n);
         let mut energy: f32 = energy_max;
         for dim in [
             EnergyDimension::Column,
@@ -27,7 +29,7 @@ impl Sudoku {
         ]
         .iter()
         {
-            for index in 0..9 {
+            for index in 0..self.grid.size() {
                 energy -= f32::from(self.count_unique_elements(dim, index));
             }
         }
@@ -35,121 +37,336 @@ impl Sudoku {
     }
 
     fn count_unique_elements(&self, dim: &EnergyDimension, index: u8) -> u8 {
-        let uniq: Vec<u8> = match dim {
-            EnergyDimension::Column => self.grid.get_col(index).into_iter().unique().collect(),
-            EnergyDimension::Row => self.grid.get_row(index).into_iter().unique().collect(),
-            EnergyDimension::Parcel => self
-                .grid
-                .get_parcel(index)
-                .into_iter()
-                .flatten()
-                .unique()
-                .collect(),
+        let unit = match dim {
+            EnergyDimension::Column => Unit::Column(index),
+            EnergyDimension::Row => Unit::Row(index),
+            EnergyDimension::Parcel => Unit::Parcel(index),
         };
-        uniq.len() as u8
+        self.grid.get_used_values(&unit).len() as u8
     }
 
-    fn is_done_with_energy(&self, energy: Option<f32>) -> bool {
-        if !self.is_done() {
-            return false;
+    /// Calculates the change of the energy if the values of two fields of the
+    /// same parcel were swapped. The parcel keeps its values, so only the
+    /// rows and columns of the fields are affected, whose value counts are
+    /// tracked by the grid. This takes constant time, unlike `calc_energy`.
+    fn calc_swap_delta(&self, f1: &Field, f2: &Field) -> f32 {
+        let v1 = self.grid.get(f1);
+        let v2 = self.grid.get(f2);
+        if v1 == v2 {
+            return 0.0;
+        }
+        let mut delta = 0;
+        for (u1, u2) in [
+            (Unit::Row(f1.row), Unit::Row(f2.row)),
+            (Unit::Column(f1.column), Unit::Column(f2.column)),
+        ]
+        .iter()
+        {
+            if u1 != u2 {
+                delta += self.calc_replace_delta(u1, v1, v2) + self.calc_replace_delta(u2, v2, v1);
+            }
+        }
+        f32::from(delta)
+    }
+
+    /// Calculates the change of the energy if a value of a unit was replaced
+    /// by another one.
+    fn calc_replace_delta(&self, unit: &Unit, old_value: u8, new_value: u8) -> i8 {
+        let mut delta = 0;
+        if self.grid.count(unit, old_value) == 1 {
+            delta += 1;
+        }
+        if self.grid.count(unit, new_value) == 0 {
+            delta -= 1;
         }
+        delta
+    }
 
+    fn is_done_with_energy(&self, energy: Option<f32>) -> bool {
         // In case the energy is already known, prevent re-computation of the
         // energy, use the given value instead. Otherwise compute it.
         let energy = match energy {
             Some(val) => val,
             None => self.calc_energy(),
         };
-        energy == 0.0
+        // The energy is cheap to check and only zero if there are no
+        // conflicts, so the full check is only needed for the final state.
+        energy == 0.0 && self.is_done()
+    }
+}
+
+/// The state of a Montecarlo simulation: a sudoku whose parcels are filled
+/// with permutations of their missing values and its energy. The state is
+/// changed by swapping two mutable values of a parcel.
+#[derive(Clone)]
+pub struct Chain {
+    pub sudoku: Sudoku,
+    pub energy: f32,
+    /// The mutable fields of the parcels with at least two of them, which
+    /// allow swapping values.
+    swappable_parcels: Vec<Vec<Field>>,
+}
+
+impl Chain {
+    /// Fills the empty fields of every parcel with the values that are
+    /// missing in the parcel.
+    pub fn new(mut sudoku: Sudoku) -> Chain {
+        let size = sudoku.grid.size();
+        for pi in 0..size {
+            let mutable_fields = sudoku.grid.get_mutable_fields_of_parcel(pi);
+            let unique_values: Vec<u8> = sudoku
+                .grid
+                .get_parcel(pi)
+                .into_iter()
+                .flatten()
+                .unique()
+                .filter(|v| v > &0)
+                .collect();
+            let diff: Vec<u8> = (1..=size).filter(|v| !unique_values.contains(v)).collect();
+            for (i, field) in mutable_fields.iter().enumerate() {
+                sudoku.grid.set(field, diff[i]);
+            }
+        }
+        let swappable_parcels = (0..size)
+            .map(|pi| sudoku.grid.get_mutable_fields_of_parcel(pi))
+            .filter(|fields| fields.len() >= 2)
+            .collect();
+        let energy = sudoku.calc_energy();
+        Chain {
+            sudoku,
+            energy,
+            swappable_parcels,
+        }
+    }
+
+    /// Returns the mutable fields of every parcel that allows swapping
+    /// values.
+    pub fn get_swappable_parcels(&self) -> &[Vec<Field>] {
+        &self.swappable_parcels
+    }
+
+    /// Returns the change of the energy if the values of two fields of the
+    /// same parcel were swapped.
+    pub fn calc_swap_delta(&self, f1: &Field, f2: &Field) -> f32 {
+        self.sudoku.calc_swap_delta(f1, f2)
+    }
+
+    /// Returns whether no values can be swapped.
+    pub fn is_frozen(&self) -> bool {
+        self.swappable_parcels.is_empty()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.sudoku.is_done_with_energy(Some(self.energy))
+    }
+
+    /// Randomly permutes the mutable values of every parcel.
+    pub fn shuffle<R: Rng>(&mut self, rng: &mut R) {
+        for fields in self.swappable_parcels.iter() {
+            let mut values: Vec<u8> = fields.iter().map(|f| self.sudoku.grid.get(f)).collect();
+            values.shuffle(rng);
+            for (field, value) in fields.iter().zip(values) {
+                self.sudoku.grid.set(field, value);
+            }
+        }
+        self.energy = self.sudoku.calc_energy();
+    }
+
+    /// Creates a state that takes the values of every parcel from one of the
+    /// two states at random. Both states must belong to the same sudoku.
+    pub fn crossover<R: Rng>(&self, other: &Chain, rng: &mut R) -> Chain {
+        let mut child = self.clone();
+        for fields in self.swappable_parcels.iter() {
+            if rng.gen() {
+                for field in fields.iter() {
+                    child.sudoku.grid.set(field, other.sudoku.grid.get(field));
+                }
+            }
+        }
+        child.energy = child.sudoku.calc_energy();
+        child
+    }
+
+    /// Chooses two mutable fields of a random parcel.
+    fn choose_swap<R: Rng>(&self, rng: &mut R) -> Option<(Field, Field)> {
+        let fields = self.swappable_parcels.choose(rng)?;
+        let indices = index::sample(rng, fields.len(), 2);
+        Some((
+            fields[indices.index(0)].clone(),
+            fields[indices.index(1)].clone(),
+        ))
+    }
+
+    /// Swaps the values of two fields of the same parcel, whose energy
+    /// difference has already been calculated with `calc_swap_delta`.
+    pub fn swap(&mut self, f1: &Field, f2: &Field, delta: f32) {
+        let f1_val = self.sudoku.grid.get(f1);
+        let f2_val = self.sudoku.grid.get(f2);
+        self.sudoku.grid.set(f1, f2_val);
+        self.sudoku.grid.set(f2, f1_val);
+        self.energy += delta;
+    }
+
+    /// Swaps two values of a random parcel and keeps the swap with the
+    /// probability given by the Metropolis criterion at the given
+    /// temperature. Returns whether the swap has been kept.
+    pub fn step<R: Rng>(&mut self, rng: &mut R, temperature: f32) -> bool {
+        let (f1, f2) = match self.choose_swap(rng) {
+            Some(fields) => fields,
+            None => return false,
+        };
+        let delta = self.sudoku.calc_swap_delta(&f1, &f2);
+        let threshold: f32 = Uniform::from(0.0..1.0).sample(rng);
+        let result = (-delta / temperature).exp();
+        if result < threshold {
+            return false;
+        }
+        self.swap(&f1, &f2, delta);
+        true
+    }
+
+    /// Swaps two values of a random parcel unconditionally.
+    pub fn mutate<R: Rng>(&mut self, rng: &mut R) {
+        if let Some((f1, f2)) = self.choose_swap(rng) {
+            let delta = self.sudoku.calc_swap_delta(&f1, &f2);
+            self.swap(&f1, &f2, delta);
+        }
     }
 }
 
 pub struct Montecarlo {
     max_tries: u32,
     tries: u32,
-    temperature: f32,
-    rng: rand::prelude::ThreadRng,
+    outcome: Option<Outcome>,
+    schedule: Schedule,
+    rng: SeededRng,
+    cancel: CancellationToken,
+    observer: Option<Observer>,
 }
 
 impl Montecarlo {
-    pub fn new(max_tries: u32, temperature: f32) -> Montecarlo {
+    /// Creates a simulation that follows the annealing schedule, see
+    /// `SeededRng::new` for the seed.
+    pub fn new(max_tries: u32, schedule: Schedule, seed: Option<u64>) -> Montecarlo {
         Montecarlo {
             max_tries,
-            temperature,
+            schedule,
             tries: 0,
-            rng: rand::thread_rng(),
+            outcome: None,
+            rng: SeededRng::new(seed),
+            cancel: CancellationToken::new(),
+            observer: None,
         }
     }
 }
 
 impl Solver for Montecarlo {
-    fn is_success(&self) -> bool {
-        self.tries < self.max_tries
+    fn get_outcome(&self) -> Option<Outcome> {
+        self.outcome
     }
 
     fn get_tries(&self) -> u32 {
         self.tries
     }
 
+    fn get_seed(&self) -> Option<u64> {
+        Some(self.rng.seed())
+    }
+
     /// Solves sudoku by using a Montecarlo simulation.
     /// See details here: https://www.lptmc.jussieu.fr/user/talbot/sudoku.html
-    fn solve(&mut self, mut sudoku: Sudoku) -> Sudoku {
-        let uniform_dist = Uniform::from(0.0..1.0);
+    /// The temperature follows the simulated annealing schedule, see
+    /// `Schedule`.
+    fn solve(&mut self, sudoku: Sudoku) -> Sudoku {
+        let mut chain = Chain::new(sudoku);
+        let mut temperature = Temperature::new(self.schedule);
+        // Accepted moves since the last progress event.
+        let mut accepted = 0;
 
-        // Fill empty values with random guesses
-        for pi in 0..9 {
-            let mutable_fields = sudoku.grid.get_mutable_fields_of_parcel(pi);
-            let unique_values: Vec<u8> = sudoku
-                .grid
-                .get_parcel(pi)
-                .into_iter()
-                .flatten()
-                .unique()
-                .filter(|v| v > &0)
-                .collect();
-            let all_numbers = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
-            let diff: Vec<u8> = all_numbers
-                .into_iter()
-                .filter(|v| !unique_values.contains(v))
-                .collect();
-            for (i, field) in mutable_fields.iter().enumerate() {
-                sudoku.grid.set(field, diff[i]);
+        while !chain.is_done() {
+            if chain.is_frozen() {
+                // Nothing left to change, the sudoku cannot be solved.
+                break;
             }
-        }
-
-        let mut energy_last = sudoku.calc_energy();
-
-        while !sudoku.is_done_with_energy(Some(energy_last)) {
-            let rand_pi = self.rng.gen_range(0, 9);
-            let mut mut_fields_parcel = sudoku.grid.get_mutable_fields_of_parcel(rand_pi);
-            mut_fields_parcel.shuffle(&mut self.rng);
-            let f1 = &mut_fields_parcel[0];
-            let f2 = &mut_fields_parcel[1];
-
-            // Swap values
-            let f1_val = sudoku.grid.get(f1);
-            let f2_val = sudoku.grid.get(f2);
-            sudoku.grid.set(f1, f2_val);
-            sudoku.grid.set(f2, f1_val);
-
-            let energy = sudoku.calc_energy();
-            let threshold = uniform_dist.sample(&mut self.rng);
-            let result = ((energy_last - energy) / self.temperature).exp();
-            let reject = result < threshold;
-
-            if reject {
-                sudoku.grid.set(f1, f1_val);
-                sudoku.grid.set(f2, f2_val);
-            } else {
-                energy_last = energy;
+            if chain.step(&mut self.rng, temperature.get()) {
+                accepted += 1;
             }
 
+            temperature.update(chain.energy);
             self.tries += 1;
-            if self.tries >= self.max_tries {
+            if let Some(observer) = &mut self.observer {
+                if observer.is_due(self.tries) {
+                    observer.notify(&Progress {
+                        tries: self.tries,
+                        energy: Some(chain.energy),
+                        temperature: Some(temperature.get()),
+                        acceptance_rate: Some(accepted as f32 / observer.interval() as f32),
+                        ..Progress::default()
+                    });
+                    accepted = 0;
+                }
+            }
+            if self.tries >= self.max_tries || self.cancel.is_cancelled() {
                 break;
             }
         }
 
-        sudoku
+        self.outcome = Some(if chain.is_done() {
+            Outcome::Solved
+        } else if chain.is_frozen() {
+            Outcome::Unsolvable
+        } else {
+            Outcome::stopped(&self.cancel)
+        });
+        chain.sudoku
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    fn set_observer(&mut self, observer: Observer) {
+        self.observer = Some(observer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn it_should_stop_without_tries_if_nothing_can_be_swapped() {
+        let s: Sudoku = "1234341221434312".parse().unwrap();
+        let mut solver = Montecarlo::new(1000, Schedule::constant(0.15), Some(1));
+        solver.solve(s);
+        assert_eq!(solver.get_outcome(), Some(Outcome::Unsolvable));
+        assert_eq!(solver.get_tries(), 0);
+    }
+
+    #[test]
+    fn it_should_reproduce_seeded_runs() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku3.txt").unwrap();
+        let mut first = Montecarlo::new(2000, Schedule::constant(0.15), Some(42));
+        let mut second = Montecarlo::new(2000, Schedule::constant(0.15), Some(42));
+        let a = first.solve(s.clone());
+        let b = second.solve(s);
+        assert_eq!(first.get_seed(), Some(42));
+        assert_eq!(first.get_tries(), second.get_tries());
+        assert_eq!(a.grid.fmt(), b.grid.fmt());
+    }
+
+    #[test]
+    fn it_should_track_energy_incrementally() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku16.txt").unwrap();
+        let mut chain = Chain::new(s);
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        for _ in 0..500 {
+            chain.step(&mut rng, 1.0);
+            assert_eq!(chain.energy, chain.sudoku.calc_energy());
+        }
     }
 }
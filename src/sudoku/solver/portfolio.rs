@@ -0,0 +1,254 @@
+use log::{debug, info};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::super::Sudoku;
+use super::{CancellationToken, Observer, Outcome, Progress, Solver};
+
+/// Creates a solver on the thread that runs it, which allows solvers that
+/// cannot be sent to other threads to take part in a portfolio.
+pub type SolverFactory = Box<dyn Fn() -> Box<dyn Solver> + Send>;
+
+/// Interval in which the portfolio checks whether it has been cancelled while
+/// waiting for its solvers.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The result of a single solver of the portfolio.
+struct Run {
+    name: String,
+    outcome: Option<Outcome>,
+    tries: u32,
+    seed: Option<u64>,
+    sudoku: Sudoku,
+}
+
+pub struct Portfolio {
+    solvers: Vec<(String, SolverFactory)>,
+    tries: u32,
+    outcome: Option<Outcome>,
+    winner: Option<String>,
+    seed: Option<u64>,
+    cancel: CancellationToken,
+    /// Receives the progress of all solvers, which is shared between their
+    /// threads.
+    observer: Option<Arc<Mutex<Observer>>>,
+}
+
+impl Portfolio {
+    /// Creates a portfolio of named solvers. The same algorithm may be added
+    /// several times, e.g. to run Montecarlo simulations with different
+    /// random numbers.
+    pub fn new(solvers: Vec<(String, SolverFactory)>) -> Portfolio {
+        Portfolio {
+            solvers,
+            tries: 0,
+            outcome: None,
+            winner: None,
+            seed: None,
+            cancel: CancellationToken::new(),
+            observer: None,
+        }
+    }
+}
+
+impl Solver for Portfolio {
+    fn get_outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// Returns the tries of the winning solver, or the most tries of any
+    /// solver if none of them found a solution.
+    fn get_tries(&self) -> u32 {
+        self.tries
+    }
+
+    /// Races all solvers of the portfolio on separate threads, each against
+    /// its own copy of the sudoku. The first solution that is verified with
+    /// `Sudoku::is_done` wins and all other solvers are cancelled. The race
+    /// also ends once a solver proves that the sudoku has no solution.
+    /// A portfolio can only be run once.
+    fn solve(&mut self, sudoku: Sudoku) -> Sudoku {
+        let race = CancellationToken::new();
+        let (sender, receiver) = mpsc::channel();
+        let observer = self.observer.clone();
+        let handles: Vec<_> = self
+            .solvers
+            .drain(..)
+            .map(|(name, factory)| {
+                let sudoku = sudoku.clone();
+                let sender = sender.clone();
+                let race = race.clone();
+                let observer = observer.clone();
+                thread::spawn(move || {
+                    let mut solver = factory();
+                    solver.set_cancellation_token(race);
+                    if let Some(observer) = observer {
+                        let interval = observer.lock().unwrap().interval();
+                        let name = name.clone();
+                        solver.set_observer(Observer::new(interval, move |progress| {
+                            let progress = Progress {
+                                solver: Some(name.clone()),
+                                ..progress.clone()
+                            };
+                            observer.lock().unwrap().notify(&progress);
+                        }));
+                    }
+                    let sudoku = solver.solve(sudoku);
+                    let run = Run {
+                        name,
+                        outcome: solver.get_outcome(),
+                        tries: solver.get_tries(),
+                        seed: solver.get_seed(),
+                        sudoku,
+                    };
+                    // The receiver is gone once another solver has won.
+                    sender.send(run).ok();
+                })
+            })
+            .collect();
+        drop(sender);
+
+        let mut solution = None;
+        loop {
+            match receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(run) if run.outcome == Some(Outcome::Solved) && run.sudoku.is_done() => {
+                    info!("{} solved the sudoku first.", run.name);
+                    race.cancel();
+                    self.tries = run.tries;
+                    self.winner = Some(run.name);
+                    self.seed = run.seed;
+                    self.outcome = Some(Outcome::Solved);
+                    solution = Some(run.sudoku);
+                    break;
+                }
+                Ok(run) if run.outcome == Some(Outcome::Unsolvable) => {
+                    // A single proof is enough, the other solvers cannot
+                    // succeed either.
+                    info!("{} proved that the sudoku has no solution.", run.name);
+                    race.cancel();
+                    self.tries = run.tries;
+                    self.outcome = Some(Outcome::Unsolvable);
+                    break;
+                }
+                Ok(run) => {
+                    debug!("{} failed after {} tries.", run.name, run.tries);
+                    self.tries = self.tries.max(run.tries);
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if self.cancel.is_cancelled() {
+                        race.cancel();
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        for handle in handles {
+            handle.join().expect("solver thread panicked");
+        }
+
+        if self.outcome.is_none() {
+            self.outcome = Some(Outcome::stopped(&self.cancel));
+        }
+        solution.unwrap_or(sudoku)
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    /// Passes the progress of every solver to the observer, together with
+    /// the name of the solver.
+    fn set_observer(&mut self, observer: Observer) {
+        self.observer = Some(Arc::new(Mutex::new(observer)));
+    }
+
+    fn get_winner(&self) -> Option<&str> {
+        self.winner.as_deref()
+    }
+
+    /// Returns the seed of the winning solver.
+    fn get_seed(&self) -> Option<u64> {
+        self.seed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Backtracing, Dlx};
+    use super::*;
+
+    #[test]
+    fn it_should_report_the_winner() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku1.txt").unwrap();
+        let mut solver = Portfolio::new(vec![
+            (
+                String::from("dlx"),
+                Box::new(|| Box::new(Dlx::new(100_000)) as Box<dyn Solver>),
+            ),
+            (
+                String::from("backtracing"),
+                Box::new(|| Box::new(Backtracing::new(100_000)) as Box<dyn Solver>),
+            ),
+        ]);
+        let s = solver.solve(s);
+        assert!(solver.is_success());
+        assert!(s.is_done());
+        assert!(["dlx", "backtracing"].contains(&solver.get_winner().unwrap()));
+    }
+
+    #[test]
+    fn it_should_pass_on_the_progress_of_its_solvers() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku1.txt").unwrap();
+        let events = Arc::new(Mutex::new(vec![]));
+        let mut solver = Portfolio::new(vec![(
+            String::from("backtracing"),
+            Box::new(|| Box::new(Backtracing::new(100_000)) as Box<dyn Solver>),
+        )]);
+        let sink = Arc::clone(&events);
+        solver.set_observer(Observer::new(20, move |progress| {
+            sink.lock().unwrap().push(progress.clone())
+        }));
+        solver.solve(s);
+        let events = events.lock().unwrap();
+        let tries: Vec<u32> = events.iter().map(|progress| progress.tries).collect();
+        assert_eq!(tries, vec![20, 40, 60]);
+        assert!(events
+            .iter()
+            .all(|progress| progress.solver.as_deref() == Some("backtracing")));
+    }
+
+    #[test]
+    fn it_should_fail_if_no_solver_succeeds() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku1.txt").unwrap();
+        let mut solver = Portfolio::new(vec![(
+            String::from("backtracing"),
+            Box::new(|| Box::new(Backtracing::new(10)) as Box<dyn Solver>),
+        )]);
+        solver.solve(s);
+        assert_eq!(solver.get_outcome(), Some(Outcome::Exhausted));
+        assert_eq!(solver.get_winner(), None);
+        assert_eq!(solver.get_tries(), 10);
+    }
+
+    #[test]
+    fn it_should_stop_once_a_solver_proves_there_is_no_solution() {
+        let s: Sudoku = "1.3..4.......3.4".parse().unwrap();
+        let mut solver = Portfolio::new(vec![
+            (
+                String::from("dlx"),
+                Box::new(|| Box::new(Dlx::new(100_000)) as Box<dyn Solver>),
+            ),
+            (
+                String::from("backtracing"),
+                Box::new(|| Box::new(Backtracing::new(100_000)) as Box<dyn Solver>),
+            ),
+        ]);
+        solver.solve(s);
+        assert_eq!(solver.get_outcome(), Some(Outcome::Unsolvable));
+        assert_eq!(solver.get_winner(), None);
+    }
+}
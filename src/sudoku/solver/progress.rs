@@ -0,0 +1,103 @@
+use std::fmt;
+
+/// A snapshot of a running solver. Solvers only fill in the values that are
+/// meaningful for their algorithm.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Progress {
+    /// The name of the solver of a portfolio that reported the progress.
+    pub solver: Option<String>,
+    pub tries: u32,
+    /// The energy of the current state of a Montecarlo simulation or a local
+    /// search, or the number of empty fields of the best ant colony solution.
+    pub energy: Option<f32>,
+    pub temperature: Option<f32>,
+    /// The number of nested guesses of a search algorithm.
+    pub depth: Option<usize>,
+    /// The share of accepted moves of a Montecarlo simulation since the
+    /// previous event, or of accepted exchanges in the last round of
+    /// parallel tempering.
+    pub acceptance_rate: Option<f32>,
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(solver) = &self.solver {
+            write!(f, "solver: {}, ", solver)?;
+        }
+        write!(f, "tries: {}", self.tries)?;
+        if let Some(energy) = self.energy {
+            write!(f, ", energy: {}", energy)?;
+        }
+        if let Some(temperature) = self.temperature {
+            write!(f, ", temperature: {:.4}", temperature)?;
+        }
+        if let Some(depth) = self.depth {
+            write!(f, ", depth: {}", depth)?;
+        }
+        if let Some(rate) = self.acceptance_rate {
+            write!(f, ", acceptance rate: {:.1}%", rate * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Receives the progress of a solver every `interval` tries.
+pub struct Observer {
+    interval: u32,
+    callback: Box<dyn FnMut(&Progress) + Send>,
+}
+
+impl Observer {
+    pub fn new<F: FnMut(&Progress) + Send + 'static>(interval: u32, callback: F) -> Observer {
+        Observer {
+            interval,
+            callback: Box::new(callback),
+        }
+    }
+
+    pub fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    /// Returns whether an event is due after the given number of tries.
+    /// Solvers check this right after counting a try, so that every event is
+    /// sent only once.
+    pub fn is_due(&self, tries: u32) -> bool {
+        tries.is_multiple_of(self.interval)
+    }
+
+    pub fn notify(&mut self, progress: &Progress) {
+        (self.callback)(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_format_progress() {
+        let progress = Progress {
+            tries: 2000,
+            energy: Some(4.0),
+            acceptance_rate: Some(0.125),
+            ..Progress::default()
+        };
+        assert_eq!(
+            format!("{}", progress),
+            "tries: 2000, energy: 4, acceptance rate: 12.5%"
+        );
+        let progress = Progress {
+            tries: 10,
+            depth: Some(3),
+            ..Progress::default()
+        };
+        assert_eq!(format!("{}", progress), "tries: 10, depth: 3");
+        let progress = Progress {
+            solver: Some(String::from("dlx")),
+            tries: 10,
+            ..Progress::default()
+        };
+        assert_eq!(format!("{}", progress), "solver: dlx, tries: 10");
+    }
+}
@@ -0,0 +1,289 @@
+use super::super::{CandidateGrid, Field, Sudoku, Unit, ValueSet};
+use super::{CancellationToken, Observer, Outcome, Progress, Solver};
+
+/// Selects the empty field to branch on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellOrder {
+    /// The field with the fewest candidates, ties are broken in row major
+    /// order.
+    MinimumRemainingValues,
+    /// The first empty field in row major order.
+    RowMajor,
+}
+
+/// Selects the order in which the candidates of a field are tried.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueOrder {
+    Ascending,
+    Descending,
+    /// The candidate that is a candidate of the fewest peers first, which
+    /// keeps the most options open for the other fields.
+    LeastConstraining,
+}
+
+pub struct Propagation {
+    max_tries: u32,
+    tries: u32,
+    outcome: Option<Outcome>,
+    cancel: CancellationToken,
+    observer: Option<Observer>,
+    cell_order: CellOrder,
+    value_order: ValueOrder,
+}
+
+impl Propagation {
+    pub fn new(max_tries: u32, cell_order: CellOrder, value_order: ValueOrder) -> Propagation {
+        Propagation {
+            max_tries,
+            tries: 0,
+            outcome: None,
+            cancel: CancellationToken::new(),
+            observer: None,
+            cell_order,
+            value_order,
+        }
+    }
+
+    /// Places naked singles (fields with a single candidate) and hidden
+    /// singles (values with a single possible field in a unit) until nothing
+    /// changes anymore. Returns `false` if a field has no candidates left or
+    /// a value has no field left in a unit, i.e. the sudoku has no solution.
+    pub fn propagate(candidates: &mut CandidateGrid) -> bool {
+        Propagation::propagate_singles(candidates, true)
+    }
+
+    /// Like `propagate`, but skips fields without candidates and values
+    /// without a field instead of stopping there, so that the remaining
+    /// fields are still propagated. The skipped fields stay empty. Returns
+    /// `false` if anything has been skipped.
+    pub fn propagate_past_contradictions(candidates: &mut CandidateGrid) -> bool {
+        Propagation::propagate_singles(candidates, false)
+    }
+
+    fn propagate_singles(candidates: &mut CandidateGrid, stop_at_contradiction: bool) -> bool {
+        let size = candidates.grid().size();
+        let mut consistent = true;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for field in Propagation::empty_fields(candidates) {
+                let field_candidates = candidates.get(&field);
+                match field_candidates.len() {
+                    0 if stop_at_contradiction => return false,
+                    0 => consistent = false,
+                    1 => {
+                        candidates.place(&field, field_candidates.first().unwrap());
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+            for unit in Unit::all(size) {
+                let used_values = candidates.grid().get_used_values(&unit);
+                let fields = candidates.grid().get_unit_fields(&unit);
+                for value in ValueSet::full(size).difference(used_values).iter() {
+                    let mut places = fields.iter().filter(|f| {
+                        candidates.grid().get(f) == 0 && candidates.get(f).contains(value)
+                    });
+                    match (places.next().cloned(), places.next()) {
+                        (None, _) if stop_at_contradiction => return false,
+                        (None, _) => consistent = false,
+                        (Some(field), None) => {
+                            candidates.place(&field, value);
+                            changed = true;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        consistent
+    }
+
+    fn empty_fields(candidates: &CandidateGrid) -> Vec<Field> {
+        let grid = candidates.grid();
+        grid.mutable_fields
+            .iter()
+            .filter(|f| grid.get(f) == 0)
+            .cloned()
+            .collect()
+    }
+
+    fn choose_field(&self, candidates: &CandidateGrid) -> Option<Field> {
+        let empty_fields = Propagation::empty_fields(candidates);
+        match self.cell_order {
+            CellOrder::MinimumRemainingValues => empty_fields
+                .into_iter()
+                .min_by_key(|f| candidates.get(f).len()),
+            CellOrder::RowMajor => empty_fields.into_iter().next(),
+        }
+    }
+
+    fn order_values(&self, candidates: &CandidateGrid, field: &Field) -> Vec<u8> {
+        let mut values: Vec<u8> = candidates.get(field).iter().collect();
+        match self.value_order {
+            ValueOrder::Ascending => {}
+            ValueOrder::Descending => values.reverse(),
+            ValueOrder::LeastConstraining => {
+                let peers = candidates.grid().get_peers(field);
+                values.sort_by_key(|v| {
+                    peers
+                        .iter()
+                        .filter(|p| candidates.grid().get(p) == 0)
+                        .filter(|p| candidates.get(p).contains(*v))
+                        .count()
+                });
+            }
+        }
+        values
+    }
+
+    /// Propagates the placed values and branches on a field chosen with the
+    /// cell ordering heuristic afterwards. Every tried candidate counts as a
+    /// try. Returns the solved candidate grid if a solution was found.
+    /// The depth is the number of guesses that led to the candidate grid.
+    fn search(&mut self, mut candidates: CandidateGrid, depth: usize) -> Option<CandidateGrid> {
+        if !Propagation::propagate(&mut candidates) {
+            return None;
+        }
+        let field = match self.choose_field(&candidates) {
+            Some(field) => field,
+            None => return Some(candidates),
+        };
+        for value in self.order_values(&candidates, &field) {
+            if self.tries >= self.max_tries || self.cancel.is_cancelled() {
+                return None;
+            }
+            self.tries += 1;
+            if let Some(observer) = &mut self.observer {
+                if observer.is_due(self.tries) {
+                    observer.notify(&Progress {
+                        tries: self.tries,
+                        depth: Some(depth),
+                        ..Progress::default()
+                    });
+                }
+            }
+            let mut guess = candidates.clone();
+            guess.place(&field, value);
+            if let Some(solution) = self.search(guess, depth + 1) {
+                return Some(solution);
+            }
+        }
+        None
+    }
+}
+
+impl Solver for Propagation {
+    fn get_outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    fn get_tries(&self) -> u32 {
+        self.tries
+    }
+
+    /// Solves the sudoku by propagating constraints with the naked and hidden
+    /// singles techniques after every placement and by branching on the
+    /// field selected with the cell ordering heuristic, see `CellOrder` and
+    /// `ValueOrder`.
+    fn solve(&mut self, mut sudoku: Sudoku) -> Sudoku {
+        let candidates = CandidateGrid::from_grid(&sudoku.grid);
+        if let Some(solution) = self.search(candidates, 0) {
+            for field in sudoku.grid.mutable_fields.clone().iter() {
+                sudoku.grid.set(field, solution.grid().get(field));
+            }
+        }
+
+        self.outcome = Some(if sudoku.is_done() {
+            Outcome::Solved
+        } else {
+            Outcome::after_search(self.tries, self.max_tries, &self.cancel)
+        });
+        sudoku
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    fn set_observer(&mut self, observer: Observer) {
+        self.observer = Some(observer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HARD: &str =
+        "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......";
+
+    #[test]
+    fn it_should_solve_without_guessing() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku1.txt").unwrap();
+        let mut solver = Propagation::new(
+            100_000,
+            CellOrder::MinimumRemainingValues,
+            ValueOrder::Ascending,
+        );
+        let s = solver.solve(s);
+        assert!(solver.is_success());
+        assert_eq!(solver.get_tries(), 0);
+        assert_eq!(s.grid.get_row(0), vec![4, 3, 5, 2, 6, 9, 7, 8, 1]);
+    }
+
+    #[test]
+    fn it_should_propagate_past_fields_without_candidates() {
+        let s: Sudoku = "................".parse().unwrap();
+        let mut candidates = CandidateGrid::from_grid(&s.grid);
+        let (dead, single) = (Field::new(0, 0), Field::new(3, 3));
+        for value in 1..=4 {
+            candidates.eliminate(&dead, value);
+        }
+        for value in 1..=3 {
+            candidates.eliminate(&single, value);
+        }
+
+        let mut strict = candidates.clone();
+        assert!(!Propagation::propagate(&mut strict));
+        assert_eq!(strict.grid().get(&single), 0);
+
+        assert!(!Propagation::propagate_past_contradictions(&mut candidates));
+        assert_eq!(candidates.grid().get(&dead), 0);
+        assert_eq!(candidates.grid().get(&single), 4);
+    }
+
+    #[test]
+    fn it_should_solve_hard_sudoku_with_all_heuristics() {
+        for cell_order in [CellOrder::MinimumRemainingValues, CellOrder::RowMajor].iter() {
+            for value_order in [
+                ValueOrder::Ascending,
+                ValueOrder::Descending,
+                ValueOrder::LeastConstraining,
+            ]
+            .iter()
+            {
+                let s: Sudoku = HARD.parse().unwrap();
+                let mut solver = Propagation::new(100_000, *cell_order, *value_order);
+                let s = solver.solve(s);
+                assert!(solver.is_success());
+                assert_eq!(s.grid.get_row(0), vec![4, 1, 7, 3, 6, 9, 8, 2, 5]);
+            }
+        }
+    }
+
+    #[test]
+    fn it_should_fail_on_unsolvable_sudoku() {
+        let s: Sudoku = "1.3..4.......3.4".parse().unwrap();
+        let mut solver = Propagation::new(
+            100_000,
+            CellOrder::MinimumRemainingValues,
+            ValueOrder::Ascending,
+        );
+        solver.solve(s);
+        assert_eq!(solver.get_outcome(), Some(Outcome::Unsolvable));
+        assert!(solver.get_tries() < 100_000);
+    }
+}
@@ -0,0 +1,406 @@
+use super::super::{Cnf, Sudoku};
+use super::{CancellationToken, Observer, Outcome, Progress, Solver};
+
+/// Result of a run of the CDCL solver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Satisfiability {
+    Satisfiable,
+    Unsatisfiable,
+    /// The maximum number of decisions has been reached or the search has
+    /// been cancelled.
+    Unknown,
+}
+
+/// A conflict-driven clause-learning SAT solver with two watched literals
+/// per clause, first-UIP learning, non-chronological backjumping and VSIDS
+/// like activities to pick decision variables.
+/// Literals are stored as `2 * variable + sign`, where variables start at 0
+/// and the sign is 1 for negated literals.
+struct Cdcl {
+    clauses: Vec<Vec<usize>>,
+    /// The clauses that watch each literal, indexed by the literal.
+    watches: Vec<Vec<usize>>,
+    assignment: Vec<Option<bool>>,
+    level: Vec<usize>,
+    /// The clause that implied the value of each variable, `None` for
+    /// decisions and top-level facts.
+    reason: Vec<Option<usize>>,
+    trail: Vec<usize>,
+    /// The position in the trail where each decision level starts.
+    trail_limits: Vec<usize>,
+    /// The position in the trail up to which literals have been propagated.
+    propagated: usize,
+    activity: Vec<f64>,
+    activity_increment: f64,
+    /// The last value of each variable, which is reused for decisions.
+    phase: Vec<bool>,
+    decisions: u32,
+    /// Set if an empty clause or contradicting unit clauses have been added.
+    contradiction: bool,
+}
+
+impl Cdcl {
+    fn new(variables: usize) -> Cdcl {
+        Cdcl {
+            clauses: vec![],
+            watches: vec![vec![]; 2 * variables],
+            assignment: vec![None; variables],
+            level: vec![0; variables],
+            reason: vec![None; variables],
+            trail: vec![],
+            trail_limits: vec![],
+            propagated: 0,
+            activity: vec![0.0; variables],
+            activity_increment: 1.0,
+            phase: vec![false; variables],
+            decisions: 0,
+            contradiction: false,
+        }
+    }
+
+    /// Converts a DIMACS literal into the internal representation.
+    fn literal(dimacs: i32) -> usize {
+        let variable = (dimacs.unsigned_abs() - 1) as usize;
+        2 * variable + usize::from(dimacs < 0)
+    }
+
+    fn value(&self, literal: usize) -> Option<bool> {
+        self.assignment[literal / 2].map(|v| v != (literal & 1 == 1))
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    /// Adds a clause of DIMACS literals before solving.
+    fn add_clause(&mut self, clause: &[i32]) {
+        let mut literals: Vec<usize> = clause.iter().map(|l| Cdcl::literal(*l)).collect();
+        literals.sort_unstable();
+        literals.dedup();
+        match literals.len() {
+            0 => self.contradiction = true,
+            1 => match self.value(literals[0]) {
+                Some(false) => self.contradiction = true,
+                Some(true) => {}
+                None => self.assign(literals[0], None),
+            },
+            _ => {
+                self.watch(self.clauses.len(), &literals);
+                self.clauses.push(literals);
+            }
+        }
+    }
+
+    fn watch(&mut self, index: usize, literals: &[usize]) {
+        self.watches[literals[0]].push(index);
+        self.watches[literals[1]].push(index);
+    }
+
+    fn assign(&mut self, literal: usize, reason: Option<usize>) {
+        let variable = literal / 2;
+        self.assignment[variable] = Some(literal & 1 == 0);
+        self.level[variable] = self.decision_level();
+        self.reason[variable] = reason;
+        self.trail.push(literal);
+    }
+
+    /// Propagates all assigned literals with unit propagation. Returns the
+    /// clause that became false if there is a conflict.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let false_literal = self.trail[self.propagated] ^ 1;
+            self.propagated += 1;
+            let mut watching = std::mem::take(&mut self.watches[false_literal]);
+            let mut i = 0;
+            while i < watching.len() {
+                let index = watching[i];
+                // Keep the false literal at the second position.
+                if self.clauses[index][0] == false_literal {
+                    self.clauses[index].swap(0, 1);
+                }
+                let first = self.clauses[index][0];
+                if self.value(first) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+                // Look for another literal to watch.
+                let replacement = (2..self.clauses[index].len())
+                    .find(|k| self.value(self.clauses[index][*k]) != Some(false));
+                if let Some(k) = replacement {
+                    self.clauses[index].swap(1, k);
+                    let literal = self.clauses[index][1];
+                    self.watches[literal].push(index);
+                    watching.swap_remove(i);
+                    continue;
+                }
+                if self.value(first) == Some(false) {
+                    self.watches[false_literal] = watching;
+                    return Some(index);
+                }
+                self.assign(first, Some(index));
+                i += 1;
+            }
+            self.watches[false_literal] = watching;
+        }
+        None
+    }
+
+    /// Derives a learnt clause from a conflict by resolving the reasons of
+    /// the literals of the current decision level until a single one (the
+    /// first unique implication point) is left. Returns the clause, whose
+    /// first literal becomes true after backjumping, and the level to jump
+    /// back to.
+    fn analyze(&mut self, conflict: usize) -> (Vec<usize>, usize) {
+        let mut seen = vec![false; self.assignment.len()];
+        let mut learnt = vec![0];
+        let mut pending = 0;
+        let mut clause = conflict;
+        let mut index = self.trail.len();
+        let uip = loop {
+            for k in 0..self.clauses[clause].len() {
+                let literal = self.clauses[clause][k];
+                let variable = literal / 2;
+                // The first literal of a reason is the implied one.
+                if seen[variable] || (clause != conflict && k == 0) || self.level[variable] == 0 {
+                    continue;
+                }
+                seen[variable] = true;
+                self.bump(variable);
+                if self.level[variable] == self.decision_level() {
+                    pending += 1;
+                } else {
+                    learnt.push(literal);
+                }
+            }
+            // Continue with the last assigned literal that has been seen.
+            loop {
+                index -= 1;
+                if seen[self.trail[index] / 2] {
+                    break;
+                }
+            }
+            let literal = self.trail[index];
+            pending -= 1;
+            if pending == 0 {
+                break literal;
+            }
+            clause = self.reason[literal / 2].unwrap();
+        };
+        learnt[0] = uip ^ 1;
+
+        // Watch the literal with the highest level besides the first one, so
+        // that the clause is watched correctly after backjumping.
+        let mut backjump_level = 0;
+        if learnt.len() > 1 {
+            let k = (1..learnt.len())
+                .max_by_key(|k| self.level[learnt[*k] / 2])
+                .unwrap();
+            learnt.swap(1, k);
+            backjump_level = self.level[learnt[1] / 2];
+        }
+        (learnt, backjump_level)
+    }
+
+    fn bump(&mut self, variable: usize) {
+        self.activity[variable] += self.activity_increment;
+        if self.activity[variable] > 1e100 {
+            for activity in self.activity.iter_mut() {
+                *activity *= 1e-100;
+            }
+            self.activity_increment *= 1e-100;
+        }
+    }
+
+    fn backjump(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        for literal in self.trail.drain(self.trail_limits[level]..) {
+            let variable = literal / 2;
+            self.phase[variable] = literal & 1 == 0;
+            self.assignment[variable] = None;
+            self.reason[variable] = None;
+        }
+        self.trail_limits.truncate(level);
+        self.propagated = self.trail.len();
+    }
+
+    /// Returns the unassigned variable with the highest activity.
+    fn choose_variable(&self) -> Option<usize> {
+        (0..self.assignment.len())
+            .filter(|v| self.assignment[*v].is_none())
+            .max_by(|a, b| self.activity[*a].partial_cmp(&self.activity[*b]).unwrap())
+    }
+
+    fn solve(
+        &mut self,
+        max_decisions: u32,
+        cancel: &CancellationToken,
+        observer: &mut Option<Observer>,
+    ) -> Satisfiability {
+        if self.contradiction {
+            return Satisfiability::Unsatisfiable;
+        }
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.decision_level() == 0 {
+                    return Satisfiability::Unsatisfiable;
+                }
+                let (learnt, backjump_level) = self.analyze(conflict);
+                self.backjump(backjump_level);
+                if learnt.len() == 1 {
+                    self.assign(learnt[0], None);
+                } else {
+                    let index = self.clauses.len();
+                    self.watch(index, &learnt);
+                    let literal = learnt[0];
+                    self.clauses.push(learnt);
+                    self.assign(literal, Some(index));
+                }
+                self.activity_increment /= 0.95;
+                continue;
+            }
+
+            let variable = match self.choose_variable() {
+                Some(variable) => variable,
+                None => return Satisfiability::Satisfiable,
+            };
+            if self.decisions >= max_decisions || cancel.is_cancelled() {
+                return Satisfiability::Unknown;
+            }
+            self.decisions += 1;
+            self.trail_limits.push(self.trail.len());
+            if let Some(observer) = observer {
+                if observer.is_due(self.decisions) {
+                    observer.notify(&Progress {
+                        tries: self.decisions,
+                        depth: Some(self.decision_level()),
+                        ..Progress::default()
+                    });
+                }
+            }
+            self.assign(2 * variable + usize::from(!self.phase[variable]), None);
+        }
+    }
+}
+
+pub struct Sat {
+    max_tries: u32,
+    tries: u32,
+    outcome: Option<Outcome>,
+    cancel: CancellationToken,
+    observer: Option<Observer>,
+}
+
+impl Sat {
+    pub fn new(max_tries: u32) -> Sat {
+        Sat {
+            max_tries,
+            tries: 0,
+            outcome: None,
+            cancel: CancellationToken::new(),
+            observer: None,
+        }
+    }
+}
+
+impl Solver for Sat {
+    fn get_outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    fn get_tries(&self) -> u32 {
+        self.tries
+    }
+
+    /// Solves the sudoku by encoding it as a boolean formula (see `Cnf`) that
+    /// is solved with a conflict-driven clause-learning SAT solver. The
+    /// values are decoded from the model afterwards. Every decision of the
+    /// SAT solver counts as a try.
+    fn solve(&mut self, mut sudoku: Sudoku) -> Sudoku {
+        let cnf = Cnf::from_sudoku(&sudoku);
+        let mut cdcl = Cdcl::new(cnf.variable_count());
+        for clause in cnf.clauses.iter() {
+            cdcl.add_clause(clause);
+        }
+        let result = cdcl.solve(self.max_tries, &self.cancel, &mut self.observer);
+        self.tries = cdcl.decisions;
+
+        if result == Satisfiability::Satisfiable {
+            for field in sudoku.grid.mutable_fields.clone().iter() {
+                let value = (1..=sudoku.grid.size())
+                    .find(|v| cdcl.value(Cdcl::literal(cnf.variable(field, *v))) == Some(true));
+                sudoku.grid.set(field, value.unwrap_or(0));
+            }
+        }
+
+        self.outcome = Some(match result {
+            Satisfiability::Satisfiable if sudoku.is_done() => Outcome::Solved,
+            Satisfiability::Unsatisfiable => Outcome::Unsolvable,
+            _ => Outcome::stopped(&self.cancel),
+        });
+        sudoku
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    fn set_observer(&mut self, observer: Observer) {
+        self.observer = Some(observer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_solve_sudoku() {
+        let mut s = Sudoku::new();
+        s.read("examples/sudoku1.txt").unwrap();
+        let mut solver = Sat::new(100_000);
+        let s = solver.solve(s);
+        assert!(solver.is_success());
+        assert_eq!(s.grid.get_row(0), vec![4, 3, 5, 2, 6, 9, 7, 8, 1]);
+    }
+
+    #[test]
+    fn it_should_solve_hard_sudoku() {
+        let s: Sudoku =
+            "4.....8.5.3..........7......2.....6.....8.4......1.......6.3.7.5..2.....1.4......"
+                .parse()
+                .unwrap();
+        let mut solver = Sat::new(100_000);
+        let s = solver.solve(s);
+        assert!(solver.is_success());
+        assert_eq!(s.grid.get_row(0), vec![4, 1, 7, 3, 6, 9, 8, 2, 5]);
+    }
+
+    #[test]
+    fn it_should_fail_on_unsolvable_sudoku() {
+        let s: Sudoku = "1.3..4.......3.4".parse().unwrap();
+        let mut solver = Sat::new(100_000);
+        solver.solve(s);
+        assert_eq!(solver.get_outcome(), Some(Outcome::Unsolvable));
+        assert!(solver.get_tries() < 100_000);
+
+        let s: Sudoku = "11..............".parse().unwrap();
+        let mut solver = Sat::new(100_000);
+        solver.solve(s);
+        assert_eq!(solver.get_outcome(), Some(Outcome::Unsolvable));
+    }
+
+    #[test]
+    fn it_should_detect_unsatisfiable_formulas() {
+        // (a or b) and (a or not b) and (not a or b) and (not a or not b)
+        let mut cdcl = Cdcl::new(2);
+        for clause in [[1, 2], [1, -2], [-1, 2], [-1, -2]].iter() {
+            cdcl.add_clause(clause);
+        }
+        assert_eq!(
+            cdcl.solve(100, &CancellationToken::new(), &mut None),
+            Satisfiability::Unsatisfiable
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use rand::seq::SliceRandom;
+
+use super::super::{Field, Sudoku};
+use super::montecarlo::Chain;
+use super::{CancellationToken, Observer, Outcome, Progress, SeededRng, Solver};
+
+/// Solves sudokus with tabu search. Like the Montecarlo simulation it keeps
+/// the parcels filled with permutations of their missing values, but it
+/// always takes the swap that lowers the energy the most, or raises it the
+/// least. Placing a value that has recently been removed from a field is
+/// tabu for `tenure` tries, unless it leads to a new lowest energy
+/// (aspiration). The search restarts from a random state if the lowest
+/// energy has not improved for `restart_after` tries.
+pub struct Tabu {
+    max_tries: u32,
+    tries: u32,
+    outcome: Option<Outcome>,
+    tenure: u32,
+    restart_after: u32,
+    rng: SeededRng,
+    cancel: CancellationToken,
+    observer: Option<Observer>,
+}
+
+impl Tabu {
+    /// Creates a tabu search that restarts from a random state after
+    /// `restart_after` tries without a new lowest energy.
+    pub fn new(max_tries: u32, tenure: u32, restart_after: u32, seed: Option<u64>) -> Tabu {
+        Tabu {
+            max_tries,
+            tries: 0,
+            outcome: None,
+            tenure,
+            restart_after,
+            rng: SeededRng::new(seed),
+            cancel: CancellationToken::new(),
+            observer: None,
+        }
+    }
+
+    /// Returns the best swaps that are not tabu or lead to a new lowest
+    /// energy, together with their energy difference.
+    fn find_best_swaps(
+        &self,
+        chain: &Chain,
+        tabu: &TabuList,
+        lowest_energy: f32,
+    ) -> (Vec<(Field, Field)>, f32) {
+        let mut best = vec![];
+        let mut best_delta = f32::INFINITY;
+        for fields in chain.get_swappable_parcels() {
+            for (i, f1) in fields.iter().enumerate() {
+                for f2 in fields[i + 1..].iter() {
+                    let delta = chain.calc_swap_delta(f1, f2);
+                    if delta > best_delta {
+                        continue;
+                    }
+                    let v1 = chain.sudoku.grid.get(f1);
+                    let v2 = chain.sudoku.grid.get(f2);
+                    let is_tabu =
+                        tabu.contains(f1, v2, self.tries) || tabu.contains(f2, v1, self.tries);
+                    if is_tabu && chain.energy + delta >= lowest_energy {
+                        continue;
+                    }
+                    if delta < best_delta {
+                        best.clear();
+                        best_delta = delta;
+                    }
+                    best.push((f1.clone(), f2.clone()));
+                }
+            }
+        }
+        (best, best_delta)
+    }
+}
+
+/// Remembers until which try a value may not be placed in a field again.
+struct TabuList {
+    size: usize,
+    until: Vec<u32>,
+}
+
+impl TabuList {
+    fn new(size: u8) -> TabuList {
+        let size = usize::from(size);
+        TabuList {
+            size,
+            until: vec![0; size * size * size],
+        }
+    }
+
+    fn index(&self, field: &Field, value: u8) -> usize {
+        (usize::from(field.row) * self.size + usize::from(field.column)) * self.size
+            + usize::from(value)
+            - 1
+    }
+
+    fn insert(&mut self, field: &Field, value: u8, until: u32) {
+        let index = self.index(field, value);
+        self.until[index] = until;
+    }
+
+    fn contains(&self, field: &Field, value: u8, tries: u32) -> bool {
+        self.until[self.index(field, value)] > tries
+    }
+
+    fn clear(&mut self) {
+        self.until.iter_mut().for_each(|until| *until = 0);
+    }
+}
+
+impl Solver for Tabu {
+    fn get_outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    fn get_tries(&self) -> u32 {
+        self.tries
+    }
+
+    fn get_seed(&self) -> Option<u64> {
+        Some(self.rng.seed())
+    }
+
+    fn solve(&mut self, sudoku: Sudoku) -> Sudoku {
+        let mut tabu = TabuList::new(sudoku.grid.size());
+        let mut chain = Chain::new(sudoku);
+        // The state with the lowest energy of all restarts.
+        let mut best = chain.clone();
+        let mut lowest_energy = chain.energy;
+        // Tries since the lowest energy of the current restart was reached.
+        let mut stale = 0;
+
+        while !chain.is_done() {
+            if chain.is_frozen() {
+                // Nothing left to change, the sudoku cannot be solved.
+                break;
+            }
+            if self.tries >= self.max_tries || self.cancel.is_cancelled() {
+                break;
+            }
+
+            if stale >= self.restart_after {
+                chain.shuffle(&mut self.rng);
+                tabu.clear();
+                lowest_energy = chain.energy;
+                stale = 0;
+            }
+
+            let (swaps, delta) = self.find_best_swaps(&chain, &tabu, lowest_energy);
+            // All swaps may be tabu if the tenure is longer than the number
+            // of possible swaps, the search waits for the tenure to pass.
+            if let Some((f1, f2)) = swaps.choose(&mut self.rng) {
+                let until = self.tries.saturating_add(self.tenure);
+                tabu.insert(f1, chain.sudoku.grid.get(f1), until);
+                tabu.insert(f2, chain.sudoku.grid.get(f2), until);
+                chain.swap(f1, f2, delta);
+            }
+            if chain.energy < lowest_energy {
+                lowest_energy = chain.energy;
+                stale = 0;
+            } else {
+                stale += 1;
+            }
+            if chain.energy < best.energy {
+                best = chain.clone();
+            }
+
+            self.tries += 1;
+            if let Some(observer) = &mut self.observer {
+                if observer.is_due(self.tries) {
+                    observer.notify(&Progress {
+                        tries: self.tries,
+                        energy: Some(chain.energy),
+                        ..Progress::default()
+                    });
+                }
+            }
+        }
+
+        self.outcome = Some(if best.is_done() {
+            Outcome::Solved
+        } else if best.is_frozen() {
+            Outcome::Unsolvable
+        } else {
+            Outcome::stopped(&self.cancel)
+        });
+        best.sudoku
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    fn set_observer(&mut self, observer: Observer) {
+        self.observer = Some(observer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_allow_tabu_swaps_to_a_new_lowest_energy() {
+        // The parcel at the top right is filled with 1 above 3, one swap
+        // away from the solution.
+        let s: Sudoku = "12.434.221434321".parse().unwrap();
+        let mut chain = Chain::new(s);
+        let solver = Tabu::new(100, 10, 50, Some(1));
+        let (f1, f2) = (Field::new(0, 2), Field::new(1, 2));
+        let mut tabu = TabuList::new(4);
+        tabu.insert(&f1, 3, 10);
+        let (swaps, delta) = solver.find_best_swaps(&chain, &tabu, chain.energy);
+        assert_eq!(swaps, vec![(f1.clone(), f2.clone())]);
+        assert!(delta < 0.0);
+
+        chain.swap(&f1, &f2, delta);
+        assert!(chain.is_done());
+        tabu.insert(&f1, 1, 10);
+        let (swaps, _) = solver.find_best_swaps(&chain, &tabu, chain.energy);
+        assert!(swaps.is_empty());
+    }
+
+    #[test]
+    fn it_should_expire_tabu_moves() {
+        let mut tabu = TabuList::new(9);
+        let field = Field::new(2, 3);
+        tabu.insert(&field, 5, 10);
+        assert!(tabu.contains(&field, 5, 9));
+        assert!(!tabu.contains(&field, 5, 10));
+        assert!(!tabu.contains(&field, 4, 9));
+        assert!(!tabu.contains(&Field::new(3, 2), 5, 9));
+    }
+}
@@ -0,0 +1,293 @@
+use rand::distributions::{Distribution, Uniform};
+use rand_chacha::ChaCha8Rng;
+use std::sync::mpsc;
+use std::thread;
+
+use super::super::Sudoku;
+use super::montecarlo::Chain;
+use super::{CancellationToken, Observer, Outcome, Progress, SeededRng, Solver};
+
+/// Solves sudokus with parallel tempering, also known as replica exchange
+/// Montecarlo: several Montecarlo simulations (replicas) of the same sudoku
+/// run at a ladder of temperatures on separate threads. After every
+/// `exchange_interval` tries neighbouring replicas swap their temperatures
+/// with the Metropolis probability of the exchange, so states that are
+/// trapped in a local minimum at a low temperature can escape at a higher
+/// one.
+pub struct Tempering {
+    max_tries: u32,
+    tries: u32,
+    outcome: Option<Outcome>,
+    /// The temperature of every replica, from the coldest to the hottest.
+    temperatures: Vec<f32>,
+    exchange_interval: u32,
+    rng: SeededRng,
+    cancel: CancellationToken,
+    observer: Option<Observer>,
+}
+
+impl Tempering {
+    /// Creates a simulation with one replica per temperature, see `ladder`.
+    pub fn new(
+        max_tries: u32,
+        temperatures: Vec<f32>,
+        exchange_interval: u32,
+        seed: Option<u64>,
+    ) -> Tempering {
+        Tempering {
+            max_tries,
+            tries: 0,
+            outcome: None,
+            temperatures,
+            exchange_interval,
+            rng: SeededRng::new(seed),
+            cancel: CancellationToken::new(),
+            observer: None,
+        }
+    }
+
+    /// Returns `count` temperatures that grow geometrically from `cold` to
+    /// `hot`, which keeps the exchange rates of neighbours similar.
+    pub fn ladder(cold: f32, hot: f32, count: usize) -> Vec<f32> {
+        match count {
+            0 => vec![],
+            1 => vec![cold],
+            _ => (0..count)
+                .map(|i| cold * (hot / cold).powf(i as f32 / (count - 1) as f32))
+                .collect(),
+        }
+    }
+
+    /// Tries to exchange the temperatures of neighbouring replicas, starting
+    /// with the coldest or the second coldest temperature in alternate
+    /// rounds. `order` holds the replica at every temperature. Returns the
+    /// number of exchanges.
+    fn exchange(
+        &self,
+        order: &mut [usize],
+        energies: &[f32],
+        round: u32,
+        rng: &mut ChaCha8Rng,
+    ) -> usize {
+        let uniform_dist = Uniform::from(0.0..1.0);
+        let mut exchanges = 0;
+        for i in ((round & 1) as usize..order.len().saturating_sub(1)).step_by(2) {
+            let beta = 1.0 / self.temperatures[i] - 1.0 / self.temperatures[i + 1];
+            let delta = beta * (energies[order[i]] - energies[order[i + 1]]);
+            let threshold: f32 = uniform_dist.sample(rng);
+            if delta.exp() >= threshold {
+                order.swap(i, i + 1);
+                exchanges += 1;
+            }
+        }
+        exchanges
+    }
+}
+
+/// The state of a replica after a round.
+struct Round {
+    replica: usize,
+    /// The number of tries of the round, which is less than requested if the
+    /// replica has found a solution or has been cancelled.
+    steps: u32,
+    energy: f32,
+    solved: bool,
+}
+
+impl Solver for Tempering {
+    fn get_outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// Returns the number of tries of every single replica.
+    fn get_tries(&self) -> u32 {
+        self.tries
+    }
+
+    fn get_seed(&self) -> Option<u64> {
+        Some(self.rng.seed())
+    }
+
+    fn solve(&mut self, sudoku: Sudoku) -> Sudoku {
+        let replicas = self.temperatures.len();
+        let origin = Chain::new(sudoku.clone());
+        if origin.is_done() {
+            self.outcome = Some(Outcome::Solved);
+            return origin.sudoku;
+        }
+        if origin.is_frozen() {
+            // Nothing left to change, the sudoku cannot be solved.
+            self.outcome = Some(Outcome::Unsolvable);
+            return sudoku;
+        }
+        // Every replica and the exchanges use their own stream of random
+        // numbers, which keeps runs reproducible regardless of the order in
+        // which the threads run.
+        let mut rngs: Vec<ChaCha8Rng> = (0..=replicas).map(|i| self.rng.stream(i as u64)).collect();
+        let mut exchange_rng = rngs.pop().unwrap();
+        // The replica at every temperature. Exchanges swap the temperatures
+        // of the replicas, which is the same as swapping their states.
+        let mut order: Vec<usize> = (0..replicas).collect();
+        let mut energies = vec![origin.energy; replicas];
+
+        let (solution, mut chains) = thread::scope(|scope| {
+            // Every replica runs on its own thread for the whole simulation
+            // and waits for the number of tries and the temperature of the
+            // next round in between.
+            let (results, finished) = mpsc::channel();
+            let workers: Vec<_> = rngs
+                .into_iter()
+                .enumerate()
+                .map(|(replica, mut rng)| {
+                    let (sender, rounds) = mpsc::channel::<(u32, f32)>();
+                    let results = results.clone();
+                    let cancel = self.cancel.clone();
+                    let mut chain = origin.clone();
+                    let handle = scope.spawn(move || {
+                        for (steps, temperature) in rounds {
+                            let mut done = 0;
+                            while done < steps && !chain.is_done() && !cancel.is_cancelled() {
+                                chain.step(&mut rng, temperature);
+                                done += 1;
+                            }
+                            let round = Round {
+                                replica,
+                                steps: done,
+                                energy: chain.energy,
+                                solved: chain.is_done(),
+                            };
+                            results.send(round).unwrap();
+                        }
+                        chain
+                    });
+                    (sender, handle)
+                })
+                .collect();
+            drop(results);
+
+            let mut round = 0;
+            let mut solution = None;
+            while solution.is_none() && replicas > 0 {
+                if self.tries >= self.max_tries || self.cancel.is_cancelled() {
+                    break;
+                }
+
+                let steps = self.exchange_interval.min(self.max_tries - self.tries);
+                for (replica, temperature) in order.iter().zip(self.temperatures.iter()) {
+                    workers[*replica].0.send((steps, *temperature)).unwrap();
+                }
+                let mut done = 0;
+                for result in finished.iter().take(replicas) {
+                    energies[result.replica] = result.energy;
+                    done = done.max(result.steps);
+                    if result.solved {
+                        solution = Some(result.replica);
+                    }
+                }
+                self.tries += done;
+                if solution.is_some() {
+                    break;
+                }
+                let exchanges = self.exchange(&mut order, &energies, round, &mut exchange_rng);
+                round += 1;
+
+                if let Some(observer) = &mut self.observer {
+                    // Rounds do not necessarily end at a multiple of the
+                    // interval, so events are sent for the first round that
+                    // passes it.
+                    let interval = observer.interval();
+                    if self.tries / interval > (self.tries - done) / interval {
+                        observer.notify(&Progress {
+                            tries: self.tries,
+                            energy: Some(energies[order[0]]),
+                            temperature: Some(self.temperatures[0]),
+                            acceptance_rate: Some(exchanges as f32 / (replicas / 2).max(1) as f32),
+                            ..Progress::default()
+                        });
+                    }
+                }
+            }
+
+            // Closing the channels of the rounds ends the threads.
+            let chains: Vec<Chain> = workers
+                .into_iter()
+                .map(|(sender, handle)| {
+                    drop(sender);
+                    handle.join().expect("replica thread panicked")
+                })
+                .collect();
+            (solution, chains)
+        });
+
+        match solution {
+            Some(replica) => {
+                self.outcome = Some(Outcome::Solved);
+                chains.swap_remove(replica).sudoku
+            }
+            None => {
+                self.outcome = Some(Outcome::stopped(&self.cancel));
+                match order.first() {
+                    Some(coldest) => chains.swap_remove(*coldest).sudoku,
+                    None => sudoku,
+                }
+            }
+        }
+    }
+
+    fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancel = token;
+    }
+
+    fn set_observer(&mut self, observer: Observer) {
+        self.observer = Some(observer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn it_should_build_geometric_ladder() {
+        let ladder = Tempering::ladder(0.1, 1.0, 3);
+        assert_eq!(ladder.len(), 3);
+        assert!((ladder[0] - 0.1).abs() < 1e-6);
+        assert!((ladder[1] - 0.316_227_76).abs() < 1e-6);
+        assert!((ladder[2] - 1.0).abs() < 1e-6);
+        assert_eq!(Tempering::ladder(0.1, 1.0, 1), vec![0.1]);
+    }
+
+    #[test]
+    fn it_should_move_lower_energies_to_colder_temperatures() {
+        let solver = Tempering::new(1000, vec![0.1, 1.0], 100, Some(1));
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        // The replica at the colder temperature has the higher energy, so
+        // the exchange is always accepted.
+        let mut order = vec![0, 1];
+        assert_eq!(solver.exchange(&mut order, &[10.0, 0.0], 0, &mut rng), 1);
+        assert_eq!(order, vec![1, 0]);
+        // The other way round it is accepted with a probability of e^-90.
+        assert_eq!(solver.exchange(&mut order, &[10.0, 0.0], 0, &mut rng), 0);
+        assert_eq!(order, vec![1, 0]);
+        // Odd rounds start at the second temperature, which has no
+        // neighbour here.
+        let mut order = vec![0, 1];
+        assert_eq!(solver.exchange(&mut order, &[10.0, 0.0], 1, &mut rng), 0);
+    }
+
+    #[test]
+    fn it_should_stop_within_a_round_once_cancelled() {
+        // The sudoku cannot be solved but values can still be swapped, so
+        // only the deadline ends the single long round.
+        let s: Sudoku = "1.3..4.......3.4".parse().unwrap();
+        let mut solver =
+            Tempering::new(u32::MAX, Tempering::ladder(0.1, 1.0, 2), u32::MAX, Some(1));
+        let deadline = Instant::now() + Duration::from_millis(20);
+        solver.set_cancellation_token(CancellationToken::with_deadline(deadline));
+        solver.solve(s);
+        assert_eq!(solver.get_outcome(), Some(Outcome::Cancelled));
+        assert!(solver.get_tries() < u32::MAX);
+    }
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A group of fields that must not contain any value twice, identified by
+/// its index. Parcels are numbered row major, see `Grid::get_parcel_index`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Unit {
+    Row(u8),
+    Column(u8),
+    Parcel(u8),
+}
+
+impl Unit {
+    /// Returns all rows, columns and parcels of a sudoku of the given size.
+    pub fn all(size: u8) -> impl Iterator<Item = Unit> {
+        (0..size)
+            .map(Unit::Row)
+            .chain((0..size).map(Unit::Column))
+            .chain((0..size).map(Unit::Parcel))
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unit::Row(index) => write!(f, "row {}", index),
+            Unit::Column(index) => write!(f, "column {}", index),
+            Unit::Parcel(index) => write!(f, "parcel {}", index),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::iter::FromIterator;
+
+/// A set of field values `1..=n`, stored as a bitmask in which bit `v - 1`
+/// represents the value `v`. Sudokus of up to 64x64 fields fit into a set.
+/// The value 0 marks empty fields and is never part of a set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ValueSet(u64);
+
+impl ValueSet {
+    /// Returns the set of all values of a sudoku of the given size, i.e.
+    /// `1..=size`.
+    pub fn full(size: u8) -> ValueSet {
+        match size {
+            64 => ValueSet(u64::MAX),
+            _ => ValueSet((1 << size) - 1),
+        }
+    }
+
+    pub fn contains(self, value: u8) -> bool {
+        value > 0 && self.0 & ValueSet::bit(value) != 0
+    }
+
+    pub fn insert(&mut self, value: u8) {
+        if value > 0 {
+            self.0 |= ValueSet::bit(value);
+        }
+    }
+
+    pub fn remove(&mut self, value: u8) {
+        if value > 0 {
+            self.0 &= !ValueSet::bit(value);
+        }
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(self, other: ValueSet) -> ValueSet {
+        ValueSet(self.0 | other.0)
+    }
+
+    pub fn difference(self, other: ValueSet) -> ValueSet {
+        ValueSet(self.0 & !other.0)
+    }
+
+    /// Returns the smallest value of the set.
+    pub fn first(self) -> Option<u8> {
+        match self.0 {
+            0 => None,
+            bits => Some(bits.trailing_zeros() as u8 + 1),
+        }
+    }
+
+    /// Returns the smallest value of the set that is greater than `value`.
+    /// This allows to walk through the values of a set without allocating.
+    pub fn next_after(self, value: u8) -> Option<u8> {
+        match value {
+            64.. => None,
+            _ => ValueSet(self.0 & !((1 << value) - 1)).first(),
+        }
+    }
+
+    /// Iterates over the values of the set in ascending order.
+    pub fn iter(self) -> impl Iterator<Item = u8> {
+        std::iter::successors(self.first(), move |v| self.next_after(*v))
+    }
+
+    fn bit(value: u8) -> u64 {
+        1 << (value - 1)
+    }
+}
+
+impl FromIterator<u8> for ValueSet {
+    fn from_iter<I: IntoIterator<Item = u8>>(values: I) -> ValueSet {
+        let mut set = ValueSet::default();
+        for value in values {
+            set.insert(value);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_contain_values() {
+        let mut set: ValueSet = vec![1, 3, 9].into_iter().collect();
+        assert!(set.contains(3));
+        assert!(!set.contains(2));
+        assert!(!set.contains(0));
+        set.remove(3);
+        set.insert(0);
+        assert_eq!(set.iter().collect::<Vec<u8>>(), vec![1, 9]);
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn it_should_walk_through_values() {
+        let set = ValueSet::full(9).difference(vec![2, 4, 6, 8].into_iter().collect());
+        assert_eq!(set.first(), Some(1));
+        assert_eq!(set.next_after(1), Some(3));
+        assert_eq!(set.next_after(4), Some(5));
+        assert_eq!(set.next_after(9), None);
+        assert_eq!(ValueSet::full(35).len(), 35);
+        assert!(ValueSet::default().is_empty());
+    }
+}